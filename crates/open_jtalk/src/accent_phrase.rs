@@ -0,0 +1,157 @@
+use super::*;
+
+/// アクセント句。
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AccentPhrase {
+    pub moras: Vec<Mora>,
+    /// アクセント核の位置(1始まり)。0は平板型。
+    pub accent: usize,
+    /// 直後にポーズ(`、`や`？`)が入るかどうか。
+    pub pause: bool,
+    /// 疑問形(`？`で終わる)かどうか。
+    pub is_interrogative: bool,
+    pub span: SourceSpan,
+}
+
+/// モーラ。
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Mora {
+    /// カタカナ表記。無声化記号(`’`)は含まない。
+    pub text: String,
+    /// 母音が無声化されているかどうか。
+    pub unvoiced: bool,
+    pub span: SourceSpan,
+}
+
+const PAUSE_PRON: &str = "、";
+const QUESTION_PRON: &str = "？";
+const UNVOICED_MARK: char = '’';
+
+impl AccentPhrase {
+    /// NJDのノード列からアクセント句を組み立てる。
+    ///
+    /// JPCommonのラベル生成と同じく、`chain_flag`が1のノードは直前のアクセント句に繋げ、
+    /// 発音が`、`のノードはポーズ、`？`のノードは疑問形かつポーズとして扱う。
+    pub(crate) fn from_aligned_nodes(nodes: &[AlignedNode]) -> Vec<Self> {
+        let mut phrases = Vec::<Self>::new();
+        let mut after_pause = true;
+        for AlignedNode { node, span } in nodes {
+            if node.pron == PAUSE_PRON {
+                if let Some(phrase) = phrases.last_mut() {
+                    phrase.pause = true;
+                }
+                after_pause = true;
+                continue;
+            }
+            if node.pron == QUESTION_PRON {
+                if let Some(phrase) = phrases.last_mut() {
+                    phrase.is_interrogative = true;
+                    phrase.pause = true;
+                }
+                after_pause = true;
+                continue;
+            }
+            let moras = moras_of_node(node, span);
+            if moras.is_empty() {
+                continue;
+            }
+            match phrases.last_mut() {
+                Some(phrase) if !after_pause && node.chain_flag == 1 => {
+                    phrase.span = phrase.span.union(span);
+                    phrase.moras.extend(moras);
+                }
+                _ => phrases.push(Self {
+                    moras,
+                    accent: node.acc.max(0) as usize,
+                    pause: false,
+                    is_interrogative: false,
+                    span: span.clone(),
+                }),
+            }
+            after_pause = false;
+        }
+        phrases
+    }
+}
+
+/// 発音をモーラに分割する。拗音などの小書き文字と無声化記号は直前のモーラに含める。
+pub(crate) fn split_moras(pron: &str) -> Vec<&str> {
+    let mut moras = vec![];
+    let mut start = 0;
+    for (i, c) in pron.char_indices() {
+        if i > 0 && !is_attached_to_previous(c) {
+            moras.push(&pron[start..i]);
+            start = i;
+        }
+    }
+    if start < pron.len() {
+        moras.push(&pron[start..]);
+    }
+    moras
+}
+
+fn is_attached_to_previous(c: char) -> bool {
+    matches!(
+        c,
+        'ァ' | 'ィ' | 'ゥ' | 'ェ' | 'ォ' | 'ャ' | 'ュ' | 'ョ' | 'ヮ' | UNVOICED_MARK
+    )
+}
+
+/// ノードの発音をモーラに分割し、それぞれに入力上の範囲を割り当てる。
+///
+/// 表層形が仮名のみで発音とモーラ数が一致する場合はモーラごとに対応する文字を割り当て、
+/// そうでなければ(漢字や数字など)ノード全体の範囲を割り当てる。
+fn moras_of_node(node: &NjdNode, span: &SourceSpan) -> Vec<Mora> {
+    let prons = split_moras(&node.pron);
+    let surface = node
+        .string
+        .chars()
+        .map(hiragana_to_katakana)
+        .collect::<String>();
+    let surface_moras =
+        if surface.chars().all(is_kana) && span.char_range.len() == surface.chars().count() {
+            split_moras(&surface)
+        } else {
+            vec![]
+        };
+    let mut char_start = span.char_range.start;
+    let mut byte_start = span.range.start;
+    prons
+        .iter()
+        .enumerate()
+        .map(|(i, pron)| {
+            let span = if surface_moras.len() == prons.len() {
+                let chars = surface_moras[i].chars();
+                let byte_len = chars.clone().map(char::len_utf8).sum::<usize>();
+                let char_len = chars.count();
+                let mora_span = SourceSpan {
+                    range: byte_start..byte_start + byte_len,
+                    char_range: char_start..char_start + char_len,
+                };
+                byte_start += byte_len;
+                char_start += char_len;
+                mora_span
+            } else {
+                span.clone()
+            };
+            Mora {
+                text: pron.trim_end_matches(UNVOICED_MARK).to_owned(),
+                unvoiced: pron.ends_with(UNVOICED_MARK),
+                span,
+            }
+        })
+        .collect()
+}
+
+fn hiragana_to_katakana(c: char) -> char {
+    match c {
+        'ぁ'..='ゖ' => char::from_u32(c as u32 + 0x60).unwrap_or(c),
+        c => c,
+    }
+}
+
+fn is_kana(c: char) -> bool {
+    matches!(c, 'ァ'..='ヺ' | 'ー')
+}
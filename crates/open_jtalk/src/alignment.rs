@@ -0,0 +1,386 @@
+use super::*;
+use std::ops::Range;
+
+/// 入力文字列上の範囲。
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SourceSpan {
+    /// バイト単位の範囲。
+    pub range: Range<usize>,
+    /// 文字(`char`)単位の範囲。
+    pub char_range: Range<usize>,
+}
+
+/// 入力上の範囲を付与したNJDのノード。
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AlignedNode {
+    pub node: NjdNode,
+    pub span: SourceSpan,
+}
+
+/// [`OpenJtalk::analyze`]の結果。
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Analysis {
+    /// MeCabによる形態素解析の結果。
+    pub morphemes: Vec<Morpheme>,
+    pub nodes: Vec<AlignedNode>,
+    pub accent_phrases: Vec<AccentPhrase>,
+    pub labels: Vec<String>,
+}
+
+impl SourceSpan {
+    pub(crate) fn union(&self, other: &Self) -> Self {
+        Self {
+            range: self.range.start.min(other.range.start)..self.range.end.max(other.range.end),
+            char_range: self.char_range.start.min(other.char_range.start)
+                ..self.char_range.end.max(other.char_range.end),
+        }
+    }
+}
+
+/// text2mecabで変換した文字列と、変換前の文字列との対応。
+pub(crate) struct ConvertedText {
+    pub(crate) text: String,
+    /// (変換後のバイト範囲, 変換前のバイト範囲)の列。変換後の範囲の順に並び、隙間なく全体を覆う。
+    segments: Vec<(Range<usize>, Range<usize>)>,
+    /// 変換前の文字列の各文字の開始位置。
+    char_starts: Vec<usize>,
+    source_len: usize,
+}
+
+impl ConvertedText {
+    pub(crate) fn new(text2mecab: &Text2Mecab, source: &str) -> Self {
+        let mut text = String::with_capacity(source.len() * 3);
+        let mut segments = vec![];
+        text2mecab.for_each_segment(source, |range, converted| {
+            let start = text.len();
+            text.push_str(converted);
+            segments.push((start..text.len(), range));
+        });
+        Self {
+            text,
+            segments,
+            char_starts: source.char_indices().map(|(i, _)| i).collect(),
+            source_len: source.len(),
+        }
+    }
+
+    /// 変換せずにそのまま使う場合。
+    pub(crate) fn identity(source: &str) -> Self {
+        Self {
+            text: source.to_owned(),
+            segments: source
+                .char_indices()
+                .map(|(i, c)| (i..i + c.len_utf8(), i..i + c.len_utf8()))
+                .collect(),
+            char_starts: source.char_indices().map(|(i, _)| i).collect(),
+            source_len: source.len(),
+        }
+    }
+
+    /// 変換後の範囲を、変換前の範囲に戻す。
+    fn source_span(&self, converted: Range<usize>) -> SourceSpan {
+        let start = self
+            .segments
+            .get(
+                self.segments
+                    .partition_point(|(c, _)| c.end <= converted.start),
+            )
+            .map_or(self.source_len, |(_, s)| s.start);
+        let end = if converted.is_empty() {
+            start
+        } else {
+            self.segments[..self
+                .segments
+                .partition_point(|(c, _)| c.start < converted.end)]
+                .last()
+                .map_or(start, |(_, s)| s.end)
+        };
+        let char_index = |byte: usize| self.char_starts.partition_point(|&s| s < byte);
+        SourceSpan {
+            range: start..end,
+            char_range: char_index(start)..char_index(end),
+        }
+    }
+}
+
+/// NJDのノードの表層形を変換後の文字列に先頭から照合し、入力上の範囲を割り当てる。
+///
+/// 各ノードは直前のノードの終わりの位置でのみ照合する。`njd_set_digit`による数字の読み替えなどで
+/// 表層形が入力と一致しなくなったノードには、その位置から続く数字の並びの範囲をまとめて割り当て、
+/// 次のノードはその後ろでも照合する。数字の並びがなければ幅0の範囲になる。
+pub(crate) fn align_nodes(converted: &ConvertedText, nodes: Vec<NjdNode>) -> Vec<AlignedNode> {
+    let text = &converted.text;
+    let mut aligned = Vec::with_capacity(nodes.len());
+    let mut pending = vec![];
+    let mut cursor = 0;
+
+    let flush =
+        |pending: &mut Vec<NjdNode>, aligned: &mut Vec<AlignedNode>, range: Range<usize>| {
+            let span = converted.source_span(range);
+            aligned.extend(pending.drain(..).map(|node| AlignedNode {
+                node,
+                span: span.clone(),
+            }));
+        };
+
+    for node in nodes {
+        cursor = skip_whitespace(text, cursor);
+        let matches =
+            |start: usize| !node.string.is_empty() && text[start..].starts_with(&node.string);
+        let found = if matches(cursor) {
+            Some(cursor)
+        } else {
+            Some(skip_number(text, cursor)).filter(|&start| !pending.is_empty() && matches(start))
+        };
+        match found {
+            Some(start) => {
+                if !pending.is_empty() {
+                    flush(&mut pending, &mut aligned, cursor..start);
+                }
+                let end = start + node.string.len();
+                aligned.push(AlignedNode {
+                    node,
+                    span: converted.source_span(start..end),
+                });
+                cursor = end;
+            }
+            None => pending.push(node),
+        }
+    }
+    if !pending.is_empty() {
+        flush(
+            &mut pending,
+            &mut aligned,
+            cursor..skip_number(text, cursor),
+        );
+    }
+    aligned
+}
+
+fn skip_whitespace(text: &str, cursor: usize) -> usize {
+    text.len() - text[cursor..].trim_start().len()
+}
+
+/// `cursor`から続く数字(桁区切りと小数点を含む)の並びの終わり。
+fn skip_number(text: &str, cursor: usize) -> usize {
+    text.len()
+        - text[cursor..]
+            .trim_start_matches(|c: char| c.is_numeric() || matches!(c, '，' | '．' | ',' | '.'))
+            .len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn node(string: &str, pron: &str, acc: i32, chain_flag: i32) -> NjdNode {
+        NjdNode {
+            string: string.to_owned(),
+            pron: pron.to_owned(),
+            acc,
+            chain_flag,
+            ..Default::default()
+        }
+    }
+
+    fn span(range: Range<usize>, char_range: Range<usize>) -> SourceSpan {
+        SourceSpan { range, char_range }
+    }
+
+    #[rstest]
+    fn align_nodes_works() {
+        let source = "ｶﾞｽ代は 123円";
+        let converted = ConvertedText::new(&Text2Mecab::default(), source);
+        assert_eq!("ガス代は　１２３円", converted.text);
+
+        // set_digitで「１２３」が「百」「二十」「三」に読み替えられた状態
+        let nodes = vec![
+            node("ガス", "ガス", 1, -1),
+            node("代", "ダイ", 0, 1),
+            node("は", "ワ", 0, 1),
+            node("百", "ヒャク", 2, 0),
+            node("二十", "ニジュー", 1, 1),
+            node("三", "サン", 0, 1),
+            node("円", "エン", 1, 1),
+        ];
+        let aligned = align_nodes(&converted, nodes);
+        assert_eq!(
+            vec![
+                span(0..9, 0..3),
+                span(9..12, 3..4),
+                span(12..15, 4..5),
+                span(16..19, 6..9),
+                span(16..19, 6..9),
+                span(16..19, 6..9),
+                span(19..22, 9..10),
+            ],
+            aligned.iter().map(|n| n.span.clone()).collect::<Vec<_>>()
+        );
+        assert_eq!("ｶﾞｽ", &source[aligned[0].span.range.clone()]);
+        assert_eq!("123", &source[aligned[3].span.range.clone()]);
+
+        let phrases = AccentPhrase::from_aligned_nodes(&aligned);
+        assert_eq!(2, phrases.len());
+        assert_eq!(span(0..15, 0..5), phrases[0].span);
+        assert_eq!(1, phrases[0].accent);
+        assert_eq!(
+            vec!["ガ", "ス", "ダ", "イ", "ワ"],
+            phrases[0]
+                .moras
+                .iter()
+                .map(|m| &*m.text)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(span(16..22, 6..10), phrases[1].span);
+    }
+
+    #[rstest]
+    fn align_nodes_does_not_match_later_literals() {
+        let source = "123円と百円";
+        let converted = ConvertedText::new(&Text2Mecab::default(), source);
+        let nodes = vec![
+            node("百", "ヒャク", 2, -1),
+            node("二十", "ニジュー", 1, 1),
+            node("三", "サン", 0, 1),
+            node("円", "エン", 1, 1),
+            node("と", "ト", 0, 0),
+            node("百", "ヒャク", 2, -1),
+            node("円", "エン", 1, 1),
+        ];
+        assert_eq!(
+            vec![
+                span(0..3, 0..3),
+                span(0..3, 0..3),
+                span(0..3, 0..3),
+                span(3..6, 3..4),
+                span(6..9, 4..5),
+                span(9..12, 5..6),
+                span(12..15, 6..7),
+            ],
+            align_nodes(&converted, nodes)
+                .iter()
+                .map(|n| n.span.clone())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[rstest]
+    fn align_nodes_falls_back_to_empty_spans() {
+        let source = "あい";
+        let converted = ConvertedText::new(&Text2Mecab::default(), source);
+        let nodes = vec![node("か", "カ", 0, -1), node("あい", "アイ", 0, 0)];
+        assert_eq!(
+            vec![span(0..0, 0..0), span(0..6, 0..2)],
+            align_nodes(&converted, nodes)
+                .iter()
+                .map(|n| n.span.clone())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[rstest]
+    fn accent_phrase_moras_are_aligned_to_kana() {
+        let source = "きょうは、がっこう？";
+        let converted = ConvertedText::new(&Text2Mecab::default(), source);
+        let nodes = vec![
+            node("きょう", "キョー", 1, -1),
+            node("は", "ワ", 0, 1),
+            node("、", "、", 0, 0),
+            node("がっこう", "ガッコー", 0, 0),
+            node("？", "？", 0, 0),
+        ];
+        let phrases = AccentPhrase::from_aligned_nodes(&align_nodes(&converted, nodes));
+        assert_eq!(2, phrases.len());
+        assert!(phrases[0].pause);
+        assert!(phrases[1].is_interrogative);
+        assert_eq!(
+            vec![
+                ("キョ", span(0..6, 0..2)),
+                ("ー", span(6..9, 2..3)),
+                ("ワ", span(9..12, 3..4)),
+            ],
+            phrases[0]
+                .moras
+                .iter()
+                .map(|m| (&*m.text, m.span.clone()))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec!["ガ", "ッ", "コ", "ー"],
+            phrases[1]
+                .moras
+                .iter()
+                .map(|m| &*m.text)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(span(24..27, 8..9), phrases[1].moras[3].span);
+    }
+
+    #[rstest]
+    fn accent_phrase_after_question_mark_is_not_chained() {
+        let source = "いく？いく";
+        let converted = ConvertedText::new(&Text2Mecab::default(), source);
+        let nodes = vec![
+            node("いく", "イク", 0, -1),
+            node("？", "？", 0, 0),
+            node("いく", "イク", 0, 1),
+        ];
+        let phrases = AccentPhrase::from_aligned_nodes(&align_nodes(&converted, nodes));
+        assert_eq!(2, phrases.len());
+        assert!(phrases[0].is_interrogative);
+        assert!(phrases[0].pause);
+        assert_eq!("イク'？、イク'", accent_phrases_to_kana(&phrases));
+    }
+
+    #[cfg(feature = "serde")]
+    #[rstest]
+    fn serde_schema_is_stable() {
+        let mora = Mora {
+            text: "シ".to_owned(),
+            unvoiced: true,
+            span: span(0..3, 0..1),
+        };
+        assert_eq!(
+            serde_json::json!({
+                "text": "シ",
+                "unvoiced": true,
+                "span": {
+                    "range": { "start": 0, "end": 3 },
+                    "char_range": { "start": 0, "end": 1 },
+                },
+            }),
+            serde_json::to_value(&mora).unwrap()
+        );
+        let phrase = AccentPhrase {
+            moras: vec![mora],
+            accent: 1,
+            pause: false,
+            is_interrogative: false,
+            span: span(0..3, 0..1),
+        };
+        let value = serde_json::to_value(&phrase).unwrap();
+        assert_eq!(
+            ["accent", "is_interrogative", "moras", "pause", "span"],
+            *value
+                .as_object()
+                .unwrap()
+                .keys()
+                .map(|k| &**k)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(phrase, serde_json::from_value(value).unwrap());
+    }
+
+    #[rstest]
+    #[case("キョーワ", &["キョ", "ー", "ワ"])]
+    #[case("シ’テ", &["シ’", "テ"])]
+    #[case("ヴァイオリン", &["ヴァ", "イ", "オ", "リ", "ン"])]
+    #[case("", &[])]
+    fn split_moras_works(#[case] pron: &str, #[case] expected: &[&str]) {
+        assert_eq!(expected, split_moras(pron));
+    }
+}
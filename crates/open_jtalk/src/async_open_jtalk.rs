@@ -0,0 +1,120 @@
+use super::*;
+use std::sync::Arc;
+
+/// [`OpenJtalkPool`]を`tokio`のブロッキング用スレッドで動かす非同期API。
+///
+/// 返されたFutureが完了前に破棄されると、処理中のパイプラインは次の段階の前で中断される。
+/// ブロッキング用スレッドでのパニックは呼び出し元で再開する。
+#[derive(Clone)]
+pub struct AsyncOpenJtalk {
+    pool: Arc<OpenJtalkPool>,
+}
+
+impl AsyncOpenJtalk {
+    pub fn new(pool: OpenJtalkPool) -> Self {
+        Self::from(Arc::new(pool))
+    }
+
+    pub fn pool(&self) -> &Arc<OpenJtalkPool> {
+        &self.pool
+    }
+
+    pub async fn extract_fullcontext(&self, text: impl Into<String>) -> Result<Vec<String>> {
+        self.extract_fullcontext_with_cancel(text, CancelFlag::new())
+            .await
+    }
+
+    /// `cancel`で中断できる[`AsyncOpenJtalk::extract_fullcontext`]。
+    pub async fn extract_fullcontext_with_cancel(
+        &self,
+        text: impl Into<String>,
+        cancel: CancelFlag,
+    ) -> Result<Vec<String>> {
+        let text = text.into();
+        let pool = self.pool.clone();
+        let mut guard = CancelOnDrop(Some(cancel.clone()));
+        let result = tokio::task::spawn_blocking(move || {
+            pool.get().extract_fullcontext_cancellable(text, &cancel)
+        })
+        .await;
+        guard.0 = None;
+        match result {
+            Ok(result) => result,
+            Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+            Err(_) => Err(Error::Cancelled),
+        }
+    }
+}
+
+impl From<Arc<OpenJtalkPool>> for AsyncOpenJtalk {
+    fn from(pool: Arc<OpenJtalkPool>) -> Self {
+        Self { pool }
+    }
+}
+
+/// Futureが途中で破棄されたときに中断を指示する。
+struct CancelOnDrop(Option<CancelFlag>);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        if let Some(cancel) = &self.0 {
+            cancel.cancel();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use camino::Utf8Path;
+    use pretty_assertions::assert_eq;
+
+    fn async_open_jtalk() -> AsyncOpenJtalk {
+        AsyncOpenJtalk::new(
+            OpenJtalkPool::new(
+                Utf8Path::new(std::env!("CARGO_MANIFEST_DIR"))
+                    .join("src/mecab/testdata/mecab_load"),
+                2,
+            )
+            .unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn async_open_jtalk_extract_fullcontext_works() {
+        let open_jtalk = async_open_jtalk();
+        let expected = open_jtalk.pool().extract_fullcontext("こんにちは").unwrap();
+        let (a, b) = tokio::join!(
+            open_jtalk.extract_fullcontext("こんにちは"),
+            open_jtalk.extract_fullcontext("こんにちは"),
+        );
+        assert_eq!(expected, a.unwrap());
+        assert_eq!(expected, b.unwrap());
+    }
+
+    #[tokio::test]
+    async fn async_open_jtalk_extract_fullcontext_with_cancel_works() {
+        let open_jtalk = async_open_jtalk();
+        let cancel = CancelFlag::new();
+        cancel.cancel();
+        assert!(matches!(
+            open_jtalk
+                .extract_fullcontext_with_cancel("こんにちは", cancel)
+                .await,
+            Err(Error::Cancelled)
+        ));
+    }
+
+    #[rstest]
+    fn cancel_on_drop_works() {
+        let cancel = CancelFlag::new();
+        drop(CancelOnDrop(Some(cancel.clone())));
+        assert!(cancel.is_cancelled());
+
+        let cancel = CancelFlag::new();
+        let mut guard = CancelOnDrop(Some(cancel.clone()));
+        guard.0 = None;
+        drop(guard);
+        assert!(!cancel.is_cancelled());
+    }
+}
@@ -0,0 +1,195 @@
+use crate::DicArgs;
+use anyhow::{bail, Context as _};
+use camino::{Utf8Path, Utf8PathBuf};
+use open_jtalk::{FullContextLabel, OpenJtalk, ProsodySymbol};
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufWriter, Write},
+};
+
+/// 一度に処理する発話の数。
+const CHUNK_SIZE: usize = 1024;
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct CorpusArgs {
+    /// `id|text`の形式の書き起こし。拡張子が`.tsv`ならタブ区切りとして読む。
+    transcript: Utf8PathBuf,
+
+    #[command(flatten)]
+    dic: DicArgs,
+
+    /// 出力先のディレクトリ。
+    #[arg(short, long)]
+    output: Utf8PathBuf,
+
+    /// 並列に処理するスレッド数。省略するとCPUの数。
+    #[arg(short, long)]
+    jobs: Option<usize>,
+}
+
+/// 書き起こしの1行。
+#[derive(PartialEq, Eq, Debug)]
+struct Utterance<'a> {
+    id: &'a str,
+    text: &'a str,
+}
+
+/// 書き起こしを並列に解析し、次のファイルを`output`に書き出す。
+///
+/// - `lab/<id>.lab`: フルコンテキストラベル
+/// - `phonemes.txt`: 音素を空白で区切った書き起こし
+/// - `prosody.txt`: ESPnetの`pyopenjtalk_prosody`と同じ韻律記号付きの書き起こし
+pub(crate) fn run(args: CorpusArgs) -> anyhow::Result<()> {
+    if let Some(jobs) = args.jobs {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(jobs)
+            .build_global()?;
+    }
+    let delimiter = if args.transcript.extension() == Some("tsv") {
+        '\t'
+    } else {
+        '|'
+    };
+    let transcript = std::fs::read_to_string(&args.transcript)
+        .with_context(|| format!("failed to read `{}`", args.transcript))?;
+    let utterances = parse_transcript(&transcript, &args.transcript, delimiter)?;
+
+    let lab_dir = args.output.join("lab");
+    std::fs::create_dir_all(&lab_dir).with_context(|| format!("failed to create `{lab_dir}`"))?;
+    let mut phonemes = create(&args.output.join("phonemes.txt"))?;
+    let mut prosody = create(&args.output.join("prosody.txt"))?;
+
+    let mut open_jtalk = OpenJtalk::from_model(&args.dic.load()?)?;
+    let mut failures = 0;
+    for chunk in utterances.chunks(CHUNK_SIZE) {
+        let texts = chunk.iter().map(|u| u.text).collect::<Vec<_>>();
+        for (utterance, labels) in chunk
+            .iter()
+            .zip(open_jtalk.extract_fullcontext_batch(&texts))
+        {
+            let result = labels.map_err(Into::into).and_then(|labels| {
+                let lab = lab_dir.join(format!("{}.lab", utterance.id));
+                std::fs::write(
+                    &lab,
+                    labels.iter().map(|l| l.clone() + "\n").collect::<String>(),
+                )
+                .with_context(|| format!("failed to write `{lab}`"))?;
+                let labels = labels
+                    .iter()
+                    .map(|label| label.parse())
+                    .collect::<open_jtalk::Result<Vec<FullContextLabel>>>()?;
+                let id = utterance.id;
+                let phoneme_line = labels
+                    .iter()
+                    .map(|label| label.phoneme().unwrap_or_default())
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                writeln!(phonemes, "{id}{delimiter}{phoneme_line}")?;
+                let prosody_line = ProsodySymbol::from_labels(&labels, true)
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                writeln!(prosody, "{id}{delimiter}{prosody_line}")?;
+                anyhow::Ok(())
+            });
+            if let Err(e) = result {
+                eprintln!("{}: {e:#}", utterance.id);
+                failures += 1;
+            }
+        }
+    }
+    phonemes.flush()?;
+    prosody.flush()?;
+    if failures > 0 {
+        bail!(
+            "failed to process {failures} of {} utterance(s)",
+            utterances.len()
+        );
+    }
+    Ok(())
+}
+
+impl<'a> Utterance<'a> {
+    fn parse(line: &'a str, delimiter: char) -> anyhow::Result<Self> {
+        let Some((id, text)) = line.split_once(delimiter) else {
+            bail!("expected `id{delimiter}text`: {line:?}");
+        };
+        if id.is_empty() || id.contains(['/', '\\']) || id.starts_with('.') {
+            bail!("invalid utterance id: {id:?}");
+        }
+        Ok(Self { id, text })
+    }
+}
+
+/// 書き起こし全体を読む。エラーには`path`と行番号を付ける。IDの重複はエラーとする。
+fn parse_transcript<'a>(
+    transcript: &'a str,
+    path: &Utf8Path,
+    delimiter: char,
+) -> anyhow::Result<Vec<Utterance<'a>>> {
+    let mut utterances = vec![];
+    // IDから、それが現れた行番号へ
+    let mut lines_by_id = HashMap::new();
+    for (i, line) in transcript.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let utterance =
+            Utterance::parse(line, delimiter).with_context(|| format!("{path}:{}", i + 1))?;
+        if let Some(first) = lines_by_id.insert(utterance.id, i + 1) {
+            bail!(
+                "{path}:{}: duplicate utterance id {:?} (first seen at line {first})",
+                i + 1,
+                utterance.id,
+            );
+        }
+        utterances.push(utterance);
+    }
+    Ok(utterances)
+}
+
+fn create(path: &Utf8Path) -> anyhow::Result<BufWriter<File>> {
+    Ok(BufWriter::new(
+        File::create(path).with_context(|| format!("failed to create `{path}`"))?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("a|こんにちは", '|', Some(Utterance { id: "a", text: "こんにちは" }))]
+    #[case("a\tx|y", '\t', Some(Utterance { id: "a", text: "x|y" }))]
+    #[case("こんにちは", '|', None)]
+    #[case("|こんにちは", '|', None)]
+    #[case("../a|こんにちは", '|', None)]
+    fn utterance_parse_works(
+        #[case] line: &str,
+        #[case] delimiter: char,
+        #[case] expected: Option<Utterance<'_>>,
+    ) {
+        assert_eq!(expected, Utterance::parse(line, delimiter).ok());
+    }
+
+    #[rstest]
+    fn parse_transcript_works() {
+        let path = Utf8Path::new("transcript.txt");
+        assert_eq!(
+            vec![
+                Utterance { id: "a", text: "x" },
+                Utterance { id: "b", text: "y" },
+            ],
+            parse_transcript("a|x\n\nb|y\n", path, '|').unwrap()
+        );
+        let error = parse_transcript("a|x\nb|y\na|z\n", path, '|').unwrap_err();
+        assert_eq!(
+            r#"transcript.txt:3: duplicate utterance id "a" (first seen at line 1)"#,
+            error.to_string()
+        );
+    }
+}
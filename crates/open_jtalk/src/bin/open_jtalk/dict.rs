@@ -0,0 +1,176 @@
+use crate::DicArgs;
+use anyhow::{bail, Context as _};
+use camino::{Utf8Path, Utf8PathBuf};
+use open_jtalk::{DictionaryEntry, Text2Mecab, UserDict, DEFAULT_COST};
+use serde::Deserialize;
+
+#[derive(clap::Subcommand, Debug)]
+pub(crate) enum DictCommand {
+    /// 単語リスト(CSVまたはTOML)からユーザー辞書をコンパイルする。
+    Build {
+        /// 単語リスト。拡張子が`.toml`ならTOML、それ以外はMeCabのCSVとして読む。
+        input: Utf8PathBuf,
+
+        /// システム辞書のディレクトリ。
+        #[arg(long)]
+        dic: Utf8PathBuf,
+
+        /// 出力先。
+        #[arg(short, long)]
+        output: Utf8PathBuf,
+    },
+    /// 辞書の情報を表示する。
+    Info {
+        #[command(flatten)]
+        dic: DicArgs,
+    },
+    /// 表層形が一致する辞書の項目を表示する。
+    Lookup {
+        word: String,
+
+        #[command(flatten)]
+        dic: DicArgs,
+    },
+    /// 単語リストの問題(カタカナでない発音、範囲外のアクセント、重複した表層形など)を表示する。
+    Lint { input: Utf8PathBuf },
+}
+
+/// TOMLの単語リスト。
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WordList {
+    words: Vec<Word>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Word {
+    surface: String,
+    pronunciation: String,
+    accent_type: i32,
+    #[serde(default = "default_cost")]
+    cost: i16,
+}
+
+fn default_cost() -> i16 {
+    DEFAULT_COST
+}
+
+impl DictCommand {
+    pub(crate) fn run(self) -> anyhow::Result<()> {
+        match self {
+            Self::Build { input, dic, output } => {
+                let dict = read_word_list(&input)?;
+                print_lint(&input, &dict);
+                dict.build(&dic, &output)
+                    .with_context(|| format!("failed to build `{output}`"))?;
+            }
+            Self::Info { dic } => {
+                for info in dic.load()?.dictionary_info()? {
+                    println!("filename: {}", info.filename);
+                    println!("kind: {:?}", info.kind);
+                    println!("charset: {}", info.charset);
+                    println!("size: {}", info.size);
+                    println!("left size: {}", info.left_size);
+                    println!("right size: {}", info.right_size);
+                    println!("version: {}", info.version);
+                    println!();
+                }
+            }
+            Self::Lookup { word, dic } => {
+                let entries = dic.load()?.lookup(&Text2Mecab::default().convert(&word))?;
+                if entries.is_empty() {
+                    bail!("{word:?} was not found");
+                }
+                for entry in entries {
+                    println!("{}", entry.to_csv());
+                }
+            }
+            Self::Lint { input } => {
+                let count = print_lint(&input, &read_word_list(&input)?);
+                if count > 0 {
+                    bail!("found {count} issue(s)");
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn read_word_list(path: &Utf8Path) -> anyhow::Result<UserDict> {
+    let text = std::fs::read_to_string(path).with_context(|| format!("failed to read `{path}`"))?;
+    let dict = if path.extension() == Some("toml") {
+        parse_toml(&text)
+    } else {
+        UserDict::parse_csv(&text).map_err(Into::into)
+    };
+    dict.with_context(|| format!("failed to parse `{path}`"))
+}
+
+fn parse_toml(text: &str) -> anyhow::Result<UserDict> {
+    let WordList { words } = toml::from_str(text)?;
+    Ok(words
+        .into_iter()
+        .map(|word| DictionaryEntry {
+            cost: word.cost,
+            ..DictionaryEntry::new(&word.surface, &word.pronunciation, word.accent_type)
+        })
+        .collect())
+}
+
+/// 問題を標準エラー出力に書き、その数を返す。
+fn print_lint(path: &Utf8Path, dict: &UserDict) -> usize {
+    let issues = dict.lint();
+    for issue in &issues {
+        eprintln!(
+            "{path}: entry {} ({}): {}",
+            issue.index + 1,
+            dict.entries[issue.index].morpheme.string,
+            issue.kind,
+        );
+    }
+    issues.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    fn parse_toml_works() {
+        let dict = parse_toml(
+            r#"
+[[words]]
+surface = "VOICEVOX"
+pronunciation = "ボイスボックス"
+accent_type = 5
+
+[[words]]
+surface = "ずんだもん"
+pronunciation = "ズンダモン"
+accent_type = 1
+cost = 100
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            UserDict {
+                entries: vec![
+                    DictionaryEntry::new("VOICEVOX", "ボイスボックス", 5),
+                    DictionaryEntry {
+                        cost: 100,
+                        ..DictionaryEntry::new("ずんだもん", "ズンダモン", 1)
+                    },
+                ]
+            },
+            dict
+        );
+    }
+
+    #[rstest]
+    fn parse_toml_rejects_unknown_fields() {
+        assert!(parse_toml("[[words]]\nsurface = \"a\"\nreading = \"ア\"\n").is_err());
+    }
+}
@@ -0,0 +1,133 @@
+//! テキストからフルコンテキストラベルなどを出力するコマンド。
+
+mod corpus;
+mod dict;
+mod output;
+mod repl;
+
+use anyhow::Context as _;
+use camino::Utf8PathBuf;
+use clap::{CommandFactory as _, Parser, Subcommand};
+use corpus::CorpusArgs;
+use dict::DictCommand;
+use open_jtalk::{MecabModel, OpenJtalk};
+use output::Format;
+use repl::ReplArgs;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+
+/// テキストを1行ずつ解析し、フルコンテキストラベル・音素・カナ・JSONのいずれかを出力する。
+#[derive(Parser, Debug)]
+#[command(version, args_conflicts_with_subcommands = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    run: Option<RunArgs>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// ユーザー辞書を扱う。
+    Dict {
+        #[command(subcommand)]
+        command: DictCommand,
+    },
+    /// 辞書を読み込んだまま、入力した行の解析結果を段階ごとに表示する。
+    Repl(ReplArgs),
+    /// 書き起こしから学習用のラベルと韻律記号付きの書き起こしを作る。
+    Corpus(CorpusArgs),
+}
+
+#[derive(clap::Args, Debug)]
+struct RunArgs {
+    /// 入力ファイル。省略するか`-`を指定すると標準入力から読む。
+    files: Vec<Utf8PathBuf>,
+
+    #[command(flatten)]
+    dic: DicArgs,
+
+    #[arg(short, long, value_enum, default_value_t = Format::Labels)]
+    format: Format,
+}
+
+#[derive(clap::Args, Debug)]
+struct DicArgs {
+    /// システム辞書のディレクトリ。
+    #[arg(long)]
+    dic: Utf8PathBuf,
+
+    /// コンパイル済みのユーザー辞書。
+    #[arg(long)]
+    userdic: Option<Utf8PathBuf>,
+}
+
+impl DicArgs {
+    fn load(&self) -> anyhow::Result<MecabModel> {
+        MecabModel::load_with_userdic(&self.dic, self.userdic.as_deref())
+            .with_context(|| format!("failed to load dictionary `{}`", self.dic))
+    }
+}
+
+fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+    match (cli.command, cli.run) {
+        (Some(Command::Dict { command }), _) => command.run(),
+        (Some(Command::Repl(args)), _) => repl::run(args),
+        (Some(Command::Corpus(args)), _) => corpus::run(args),
+        (None, Some(args)) => run(args),
+        (None, None) => Ok(Cli::command().print_help()?),
+    }
+}
+
+fn run(args: RunArgs) -> anyhow::Result<()> {
+    let mut open_jtalk = OpenJtalk::from_model(&args.dic.load()?)?;
+
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    let files = if args.files.is_empty() {
+        vec!["-".into()]
+    } else {
+        args.files
+    };
+    for file in &files {
+        let reader: Box<dyn BufRead> = if file == "-" {
+            Box::new(io::stdin().lock())
+        } else {
+            Box::new(BufReader::new(
+                std::fs::File::open(file).with_context(|| format!("failed to open `{file}`"))?,
+            ))
+        };
+        for line in reader.lines() {
+            let line = line.with_context(|| format!("failed to read `{file}`"))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            args.format
+                .write(&mut out, &mut open_jtalk, &line)
+                .with_context(|| format!("failed to process {line:?}"))?;
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    fn cli_is_valid() {
+        Cli::command().debug_assert();
+    }
+
+    #[rstest]
+    #[case(&["open_jtalk", "dict", "lint", "words.csv"])]
+    #[case(&["open_jtalk", "--dic", "dic", "input.txt"])]
+    #[case(&["open_jtalk", "repl", "--dic", "dic"])]
+    #[case(&["open_jtalk", "corpus", "text.tsv", "--dic", "dic", "-o", "out", "-j", "4"])]
+    fn cli_parses_subcommands_and_default_mode(#[case] args: &[&str]) {
+        Cli::try_parse_from(args).unwrap();
+    }
+}
@@ -0,0 +1,76 @@
+use open_jtalk::{accent_phrases_to_kana, Analysis, FullContextLabel, OpenJtalk};
+use serde::Serialize;
+use std::io::Write;
+
+#[derive(clap::ValueEnum, Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum Format {
+    /// フルコンテキストラベルを1行に1つ出力する。
+    Labels,
+    /// 音素を空白区切りで出力する。
+    Phonemes,
+    /// 韻律記号付きの音素を空白区切りで出力する。
+    Prosody,
+    /// AquesTalk風の記法(`コンニチワ'、ミ_ナ'サン`)で出力する。
+    Kana,
+    /// [`Analysis`]を1行に1つのJSONとして出力する。
+    Json,
+}
+
+#[derive(Serialize)]
+struct JsonLine<'a> {
+    text: &'a str,
+    #[serde(flatten)]
+    analysis: &'a Analysis,
+}
+
+impl Format {
+    pub(crate) fn write(
+        self,
+        out: &mut impl Write,
+        open_jtalk: &mut OpenJtalk,
+        text: &str,
+    ) -> anyhow::Result<()> {
+        match self {
+            Self::Labels => {
+                for label in open_jtalk.extract_fullcontext(text)? {
+                    writeln!(out, "{label}")?;
+                }
+            }
+            Self::Phonemes => {
+                let phonemes = open_jtalk
+                    .extract_fullcontext(text)?
+                    .iter()
+                    .map(|label| {
+                        let label = label.parse::<FullContextLabel>()?;
+                        Ok(label.phoneme().unwrap_or_default().to_owned())
+                    })
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+                writeln!(out, "{}", phonemes.join(" "))?;
+            }
+            Self::Prosody => {
+                let symbols = open_jtalk
+                    .extract_prosody(text, true)?
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>();
+                writeln!(out, "{}", symbols.join(" "))?;
+            }
+            Self::Kana => {
+                let analysis = open_jtalk.analyze(text)?;
+                writeln!(out, "{}", accent_phrases_to_kana(&analysis.accent_phrases))?;
+            }
+            Self::Json => {
+                let analysis = open_jtalk.analyze(text)?;
+                serde_json::to_writer(
+                    &mut *out,
+                    &JsonLine {
+                        text,
+                        analysis: &analysis,
+                    },
+                )?;
+                writeln!(out)?;
+            }
+        }
+        Ok(())
+    }
+}
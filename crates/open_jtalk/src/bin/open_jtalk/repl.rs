@@ -0,0 +1,347 @@
+use crate::DicArgs;
+use anyhow::bail;
+use open_jtalk::{AccentPhrase, Analysis, Morpheme, NjdNode, OpenJtalk, Stage, NJD_STAGES};
+use serde_json::json;
+use std::{
+    collections::BTreeSet,
+    fmt,
+    io::{self, BufRead, Write},
+    sync::{Arc, Mutex, PoisonError},
+};
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct ReplArgs {
+    #[command(flatten)]
+    dic: DicArgs,
+}
+
+/// 表示する解析結果の種類。
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+enum Section {
+    Mecab,
+    Njd,
+    Accent,
+    Labels,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum ReplFormat {
+    Text,
+    Json,
+}
+
+#[derive(PartialEq, Eq, Debug)]
+enum Input<'a> {
+    Text(&'a str),
+    /// `:stage`。表示する種類を列挙する。
+    ListSections,
+    /// `:stage <名前>`。表示するかどうかを切り替える。
+    ToggleSection(Section),
+    /// `:format <text|json>`。
+    SetFormat(ReplFormat),
+    Help,
+    Quit,
+}
+
+/// 各段階の後のNJD。
+type NjdDumps = Arc<Mutex<Vec<NjdDump>>>;
+
+struct NjdDump {
+    stage: Stage,
+    nodes: Vec<NjdNode>,
+    /// [`open_jtalk::Njd::to_debug_string`]の出力。
+    text: String,
+}
+
+const HELP: &str = "\
+:stage            表示する種類を列挙する
+:stage <name>     mecab, njd, accent, labelsの表示を切り替える
+:format <format>  text, jsonのいずれかで出力する
+:help             このヘルプを表示する
+:quit             終了する";
+
+struct Repl {
+    open_jtalk: OpenJtalk,
+    njd_dumps: NjdDumps,
+    sections: BTreeSet<Section>,
+    format: ReplFormat,
+}
+
+pub(crate) fn run(args: ReplArgs) -> anyhow::Result<()> {
+    let mut repl = Repl::new(OpenJtalk::from_model(&args.dic.load()?)?)?;
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let mut lines = stdin.lock().lines();
+    loop {
+        write!(out, "> ")?;
+        out.flush()?;
+        let Some(line) = lines.next().transpose()? else {
+            writeln!(out)?;
+            return Ok(());
+        };
+        match Input::parse(&line) {
+            Ok(Input::Quit) => return Ok(()),
+            Ok(input) => {
+                if let Err(e) = repl.handle(&mut out, input) {
+                    eprintln!("error: {e:#}");
+                }
+            }
+            Err(e) => eprintln!("error: {e:#}"),
+        }
+    }
+}
+
+impl Repl {
+    fn new(open_jtalk: OpenJtalk) -> anyhow::Result<Self> {
+        let njd_dumps = NjdDumps::default();
+        let mut builder = open_jtalk.pipeline().to_builder();
+        for stage in [Stage::Mecab2Njd].into_iter().chain(NJD_STAGES) {
+            let njd_dumps = njd_dumps.clone();
+            builder = builder.hook_after(stage, move |njd| {
+                let dump = NjdDump {
+                    stage,
+                    nodes: njd.nodes()?,
+                    text: njd.to_debug_string()?,
+                };
+                lock(&njd_dumps).push(dump);
+                Ok(())
+            });
+        }
+        Ok(Self {
+            open_jtalk: open_jtalk.with_pipeline(builder.build()?),
+            njd_dumps,
+            sections: [
+                Section::Mecab,
+                Section::Njd,
+                Section::Accent,
+                Section::Labels,
+            ]
+            .into(),
+            format: ReplFormat::Text,
+        })
+    }
+
+    fn handle(&mut self, out: &mut impl Write, input: Input<'_>) -> anyhow::Result<()> {
+        match input {
+            Input::Text(text) => self.analyze(out, text)?,
+            Input::ListSections => {
+                for section in &self.sections {
+                    writeln!(out, "{section}")?;
+                }
+            }
+            Input::ToggleSection(section) => {
+                if !self.sections.remove(&section) {
+                    self.sections.insert(section);
+                }
+            }
+            Input::SetFormat(format) => self.format = format,
+            Input::Help => writeln!(out, "{HELP}")?,
+            Input::Quit => {}
+        }
+        Ok(())
+    }
+
+    fn analyze(&mut self, out: &mut impl Write, text: &str) -> anyhow::Result<()> {
+        lock(&self.njd_dumps).clear();
+        let analysis = self.open_jtalk.analyze(text);
+        let njd_dumps = std::mem::take(&mut *lock(&self.njd_dumps));
+        let analysis = analysis?;
+        match self.format {
+            ReplFormat::Text => self.write_text(out, &analysis, &njd_dumps),
+            ReplFormat::Json => self.write_json(out, text, &analysis, &njd_dumps),
+        }
+    }
+
+    fn write_text(
+        &self,
+        out: &mut impl Write,
+        analysis: &Analysis,
+        njd_dumps: &[NjdDump],
+    ) -> anyhow::Result<()> {
+        for section in &self.sections {
+            match section {
+                Section::Mecab => {
+                    writeln!(out, "== mecab ==")?;
+                    for morpheme in &analysis.morphemes {
+                        writeln!(out, "{}", morpheme_line(morpheme))?;
+                    }
+                }
+                Section::Njd => {
+                    for dump in njd_dumps {
+                        writeln!(out, "== njd: {} ==", dump.stage)?;
+                        write!(out, "{}", dump.text)?;
+                    }
+                }
+                Section::Accent => {
+                    writeln!(out, "== accent ==")?;
+                    for phrase in &analysis.accent_phrases {
+                        writeln!(out, "{}", accent_phrase_line(phrase))?;
+                    }
+                }
+                Section::Labels => {
+                    writeln!(out, "== labels ==")?;
+                    for label in &analysis.labels {
+                        writeln!(out, "{label}")?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn write_json(
+        &self,
+        out: &mut impl Write,
+        text: &str,
+        analysis: &Analysis,
+        njd_dumps: &[NjdDump],
+    ) -> anyhow::Result<()> {
+        let mut value = json!({ "text": text });
+        for section in &self.sections {
+            value[section.name()] = match section {
+                Section::Mecab => json!(analysis.morphemes),
+                Section::Njd => njd_dumps
+                    .iter()
+                    .map(|dump| json!({ "stage": dump.stage.name(), "nodes": dump.nodes }))
+                    .collect(),
+                Section::Accent => json!(analysis.accent_phrases),
+                Section::Labels => json!(analysis.labels),
+            };
+        }
+        serde_json::to_writer(&mut *out, &value)?;
+        writeln!(out)?;
+        Ok(())
+    }
+}
+
+impl<'a> Input<'a> {
+    fn parse(line: &'a str) -> anyhow::Result<Self> {
+        let Some(command) = line.trim().strip_prefix(':') else {
+            return Ok(Self::Text(line));
+        };
+        let mut words = command.split_whitespace();
+        let input = match (words.next(), words.next()) {
+            (Some("stage"), None) => Self::ListSections,
+            (Some("stage"), Some(name)) => Self::ToggleSection(name.parse()?),
+            (Some("format"), Some("text")) => Self::SetFormat(ReplFormat::Text),
+            (Some("format"), Some("json")) => Self::SetFormat(ReplFormat::Json),
+            (Some("help"), None) => Self::Help,
+            (Some("quit" | "q"), None) => Self::Quit,
+            _ => bail!("unknown command {line:?} (try `:help`)"),
+        };
+        if words.next().is_some() {
+            bail!("too many arguments: {line:?}");
+        }
+        Ok(input)
+    }
+}
+
+impl Section {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Mecab => "mecab",
+            Self::Njd => "njd",
+            Self::Accent => "accent",
+            Self::Labels => "labels",
+        }
+    }
+}
+
+impl fmt::Display for Section {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl std::str::FromStr for Section {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        [Self::Mecab, Self::Njd, Self::Accent, Self::Labels]
+            .into_iter()
+            .find(|section| section.name() == s)
+            .ok_or_else(|| anyhow::anyhow!("unknown stage {s:?}"))
+    }
+}
+
+fn lock(njd_dumps: &NjdDumps) -> std::sync::MutexGuard<'_, Vec<NjdDump>> {
+    njd_dumps.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// MeCabの出力と同じく、表層形とタブに続けて素性を並べる。
+fn morpheme_line(m: &Morpheme) -> String {
+    format!(
+        "{}\t{},{},{},{},{},{},{},{},{},{}/{},{}",
+        m.string,
+        m.pos,
+        m.pos_group1,
+        m.pos_group2,
+        m.pos_group3,
+        m.ctype,
+        m.cform,
+        m.orig,
+        m.read,
+        m.pron,
+        m.acc,
+        m.mora_size,
+        m.chain_rule,
+    )
+}
+
+fn accent_phrase_line(phrase: &AccentPhrase) -> String {
+    let moras = phrase
+        .moras
+        .iter()
+        .map(|mora| {
+            if mora.unvoiced {
+                format!("{}’", mora.text)
+            } else {
+                mora.text.clone()
+            }
+        })
+        .collect::<String>();
+    let mut line = format!("{moras}\taccent={}", phrase.accent);
+    if phrase.is_interrogative {
+        line += " interrogative";
+    }
+    if phrase.pause {
+        line += " pause";
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("こんにちは", Input::Text("こんにちは"))]
+    #[case(":stage", Input::ListSections)]
+    #[case(" :stage njd ", Input::ToggleSection(Section::Njd))]
+    #[case(":format json", Input::SetFormat(ReplFormat::Json))]
+    #[case(":q", Input::Quit)]
+    fn input_parse_works(#[case] line: &str, #[case] expected: Input<'_>) {
+        assert_eq!(expected, Input::parse(line).unwrap());
+    }
+
+    #[rstest]
+    #[case(":stage foo")]
+    #[case(":format yaml")]
+    #[case(":help me")]
+    #[case(":")]
+    fn input_parse_fails(#[case] line: &str) {
+        assert!(Input::parse(line).is_err());
+    }
+
+    #[rstest]
+    fn morpheme_line_works() {
+        let feature = "こんにちは,感動詞,*,*,*,*,*,こんにちは,コンニチハ,コンニチワ,0/5,C0";
+        assert_eq!(
+            "こんにちは\t感動詞,*,*,*,*,*,こんにちは,コンニチハ,コンニチワ,0/5,C0",
+            morpheme_line(&Morpheme::parse(feature))
+        );
+    }
+}
@@ -0,0 +1,47 @@
+use super::*;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+/// 処理の中断を指示するフラグ。複製したものは同じフラグを共有する。
+///
+/// パイプラインの各段階の間で確認され、立っていれば[`Error::Cancelled`]を返す。
+#[derive(Clone, Default, Debug)]
+pub struct CancelFlag(Arc<AtomicBool>);
+
+impl CancelFlag {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn check(&self) -> Result<()> {
+        if self.is_cancelled() {
+            return Err(Error::Cancelled);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[rstest]
+    fn cancel_flag_is_shared_between_clones() {
+        let flag = CancelFlag::new();
+        let cloned = flag.clone();
+        assert!(flag.check().is_ok());
+        cloned.cancel();
+        assert!(flag.is_cancelled());
+        assert!(matches!(flag.check(), Err(Error::Cancelled)));
+    }
+}
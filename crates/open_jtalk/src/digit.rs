@@ -0,0 +1,776 @@
+use super::*;
+
+const DIGITS: [&str; 10] = [
+    "ゼロ",
+    "イチ",
+    "ニ",
+    "サン",
+    "ヨン",
+    "ゴ",
+    "ロク",
+    "ナナ",
+    "ハチ",
+    "キュー",
+];
+
+/// 電話番号のように1桁ずつ読む場合の読み。
+const PHONE_DIGITS: [&str; 10] = [
+    "ゼロ",
+    "イチ",
+    "ニー",
+    "サン",
+    "ヨン",
+    "ゴー",
+    "ロク",
+    "ナナ",
+    "ハチ",
+    "キュー",
+];
+
+/// 4桁ごとの単位。
+const GROUP_UNITS: [&str; 5] = ["", "マン", "オク", "チョー", "ケー"];
+
+/// 数の後に付く助数詞と、それに伴う読みの変化。
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Counter {
+    surface: String,
+    reading: String,
+    /// (数の読みの末尾, 置き換え後の読み(助数詞を含む))。先に追加したものを優先する。
+    rules: Vec<(String, String)>,
+    /// 数全体に対する読み(`1人`→`ヒトリ`など)。`rules`より優先する。
+    exact: Vec<(u128, String)>,
+}
+
+/// `njd_set_digit`の代わりに、数字の並びを読みに変換するNJDの処理。
+///
+/// 整数・小数・分数・助数詞付きの数・日付(`2024/10/19`)・時刻(`12:30`)・電話番号・通貨・百分率を扱う。
+/// 数字と、それに続く助数詞などは1つのノードにまとめ、アクセントは平板型(0)とする。
+///
+/// 整数や桁区切りのある数(`１，０００`)の読みは`njd_set_digit`と同じになる。`njd_set_digit`と異なり
+/// 数を語ごとのノードに分けてアクセントを付けることはしないので、アクセントは意図的に平板型としている。
+/// 日付や時刻、`20歳`(`ハタチ`)のような助数詞による読みの変化も`njd_set_digit`とは異なる。
+///
+/// ```
+/// use open_jtalk::{Counter, NumberReader, Pipeline, Stage};
+///
+/// let reader = NumberReader::default().counter(Counter::new("粒", "ツブ").exact(1, "ヒトツブ"));
+/// let pipeline = Pipeline::builder()
+///     .replace(Stage::SetDigit, move |njd| reader.apply(njd))
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct NumberReader {
+    counters: Vec<Counter>,
+    /// (数の前に付く記号, 助数詞の表層形)。`＄5`を`5ドル`と読むためのもの。
+    prefixes: Vec<(String, String)>,
+}
+
+impl Counter {
+    pub fn new(surface: impl Into<String>, reading: impl Into<String>) -> Self {
+        Self {
+            surface: surface.into(),
+            reading: reading.into(),
+            rules: vec![],
+            exact: vec![],
+        }
+    }
+
+    /// 数の読みが`suffix`で終わる場合、その部分を`replacement`(助数詞を含む)に置き換える。
+    pub fn rule(mut self, suffix: impl Into<String>, replacement: impl Into<String>) -> Self {
+        self.rules.push((suffix.into(), replacement.into()));
+        self
+    }
+
+    /// 数が`number`の場合、全体を`reading`と読む。
+    pub fn exact(mut self, number: u128, reading: impl Into<String>) -> Self {
+        self.exact.push((number, reading.into()));
+        self
+    }
+
+    pub fn surface(&self) -> &str {
+        &self.surface
+    }
+
+    pub fn reading(&self) -> &str {
+        &self.reading
+    }
+
+    fn read(&self, number: u128, number_reading: &str) -> String {
+        if let Some((_, reading)) = self.exact.iter().find(|(n, _)| *n == number) {
+            return reading.clone();
+        }
+        for (suffix, replacement) in &self.rules {
+            if let Some(stem) = number_reading.strip_suffix(&**suffix) {
+                return format!("{stem}{replacement}");
+            }
+        }
+        format!("{number_reading}{}", self.reading)
+    }
+}
+
+impl Default for NumberReader {
+    fn default() -> Self {
+        /// 「イッポン」「サンボン」のように、は行の助数詞が促音・濁音化するもの。
+        fn h_row(surface: &str, plain: &str, p: &str, b: &str) -> Counter {
+            Counter::new(surface, plain)
+                .rule("イチ", format!("イッ{p}"))
+                .rule("サン", format!("サン{b}"))
+                .rule("ロク", format!("ロッ{p}"))
+                .rule("ハチ", format!("ハッ{p}"))
+                .rule("ジュー", format!("ジュッ{p}"))
+                .rule("ヒャク", format!("ヒャッ{p}"))
+                .rule("セン", format!("セン{b}"))
+                .rule("マン", format!("マン{b}"))
+        }
+        /// 「イッコ」「ジュッカイ」のように、か行・さ行・ぱ行の助数詞の前で促音化するもの。
+        fn geminate(surface: &str, reading: &str) -> Counter {
+            Counter::new(surface, reading)
+                .rule("イチ", format!("イッ{reading}"))
+                .rule("ロク", format!("ロッ{reading}"))
+                .rule("ハチ", format!("ハッ{reading}"))
+                .rule("ジュー", format!("ジュッ{reading}"))
+                .rule("ヒャク", format!("ヒャッ{reading}"))
+        }
+
+        let counters = vec![
+            h_row("本", "ホン", "ポン", "ボン"),
+            h_row("匹", "ヒキ", "ピキ", "ビキ"),
+            h_row("杯", "ハイ", "パイ", "バイ"),
+            geminate("個", "コ"),
+            geminate("回", "カイ"),
+            geminate("歳", "サイ").exact(20, "ハタチ"),
+            geminate("冊", "サツ"),
+            Counter::new("分", "フン")
+                .rule("イチ", "イップン")
+                .rule("サン", "サンプン")
+                .rule("ヨン", "ヨンプン")
+                .rule("ロク", "ロップン")
+                .rule("ハチ", "ハップン")
+                .rule("ジュー", "ジュップン")
+                .rule("ヒャク", "ヒャップン"),
+            geminate("％", "パーセント"),
+            Counter::new("人", "ニン")
+                .exact(1, "ヒトリ")
+                .exact(2, "フタリ")
+                .rule("ヨン", "ヨニン"),
+            Counter::new("枚", "マイ"),
+            Counter::new("秒", "ビョー"),
+            Counter::new("円", "エン").rule("ヨン", "ヨエン"),
+            Counter::new("ドル", "ドル"),
+            Counter::new("年", "ネン").rule("ヨン", "ヨネン"),
+            Counter::new("月", "ガツ")
+                .rule("ヨン", "シガツ")
+                .rule("ナナ", "シチガツ")
+                .rule("キュー", "クガツ"),
+            Counter::new("日", "ニチ")
+                .exact(1, "ツイタチ")
+                .exact(2, "フツカ")
+                .exact(3, "ミッカ")
+                .exact(5, "イツカ")
+                .exact(6, "ムイカ")
+                .exact(7, "ナノカ")
+                .exact(8, "ヨーカ")
+                .exact(9, "ココノカ")
+                .exact(10, "トオカ")
+                .exact(20, "ハツカ")
+                .rule("ヨン", "ヨッカ")
+                .rule("ナナ", "シチニチ")
+                .rule("キュー", "クニチ"),
+            Counter::new("時", "ジ")
+                .rule("ヨン", "ヨジ")
+                .rule("ナナ", "シチジ")
+                .rule("キュー", "クジ"),
+        ];
+        Self {
+            counters,
+            prefixes: vec![
+                ("＄".to_owned(), "ドル".to_owned()),
+                ("￥".to_owned(), "円".to_owned()),
+            ],
+        }
+    }
+}
+
+impl NumberReader {
+    /// 助数詞を追加する。同じ表層形のものがあれば置き換える。
+    pub fn counter(mut self, counter: Counter) -> Self {
+        self.counters.retain(|c| c.surface != counter.surface);
+        self.counters.push(counter);
+        self
+    }
+
+    /// 数の前に付く記号`symbol`を、表層形が`counter`の助数詞として読む。
+    pub fn prefix(mut self, symbol: impl Into<String>, counter: impl Into<String>) -> Self {
+        let symbol = symbol.into();
+        self.prefixes.retain(|(s, _)| *s != symbol);
+        self.prefixes.push((symbol, counter.into()));
+        self
+    }
+
+    pub fn counters(&self) -> &[Counter] {
+        &self.counters
+    }
+
+    pub fn apply(&self, njd: &mut Njd) -> Result<()> {
+        let nodes = self.rewrite(njd.nodes()?);
+        njd.set_nodes(&nodes)
+    }
+
+    pub(crate) fn rewrite(&self, nodes: Vec<NjdNode>) -> Vec<NjdNode> {
+        let mut result = Vec::<NjdNode>::with_capacity(nodes.len());
+        let mut i = 0;
+        while i < nodes.len() {
+            let Some(end) = number_run(&nodes, i) else {
+                result.push(nodes[i].clone());
+                i += 1;
+                continue;
+            };
+            let text = nodes[i..end]
+                .iter()
+                .flat_map(|node| node.string.chars().filter_map(normalize))
+                .collect::<String>();
+            let prefix = result.last().and_then(|node| {
+                let (_, counter) = self.prefixes.iter().find(|(s, _)| *s == node.string)?;
+                self.find_counter(counter)
+            });
+            let suffix = nodes
+                .get(end)
+                .and_then(|node| self.find_counter(&node.string));
+            let Some((reading, counter_used)) = self.read_expression(&text, prefix.or(suffix))
+            else {
+                result.extend(nodes[i..end].iter().map(|node| self.read_single(node)));
+                i = end;
+                continue;
+            };
+
+            let mut string = String::new();
+            if counter_used && prefix.is_some() {
+                string = result.pop().map(|node| node.string).unwrap_or_default();
+            }
+            string.extend(nodes[i..end].iter().map(|node| &*node.string));
+            let mut end = end;
+            if counter_used && prefix.is_none() {
+                string.push_str(&nodes[end].string);
+                end += 1;
+            }
+            result.push(number_node(&nodes[i], string, reading));
+            i = end;
+        }
+        result
+    }
+
+    fn find_counter(&self, surface: &str) -> Option<&Counter> {
+        self.counters.iter().find(|c| c.surface == surface)
+    }
+
+    /// 数字のみのノードを整数として読む。
+    fn read_single(&self, node: &NjdNode) -> NjdNode {
+        let digits = node
+            .string
+            .chars()
+            .map(normalize)
+            .collect::<Option<String>>()
+            .filter(|s| s.bytes().all(|b| b.is_ascii_digit()));
+        match digits.as_deref().and_then(read_integer) {
+            Some(reading) => number_node(node, node.string.clone(), reading),
+            None => node.clone(),
+        }
+    }
+
+    /// 数字と記号の並びを読む。読めた場合は、読みと助数詞を使ったかどうかを返す。
+    fn read_expression(&self, text: &str, counter: Option<&Counter>) -> Option<(String, bool)> {
+        if text.contains('/') {
+            let parts = digit_parts(text, '/')?;
+            return match *parts {
+                [year, month, day] => Some((self.read_date(year, month, day)?, false)),
+                [numerator, denominator] => Some((
+                    format!(
+                        "{}ブンノ{}",
+                        read_integer(denominator)?,
+                        read_integer(numerator)?
+                    ),
+                    false,
+                )),
+                _ => None,
+            };
+        }
+        if text.contains(':') {
+            return Some((self.read_time(&digit_parts(text, ':')?)?, false));
+        }
+        if text.contains('-') {
+            return Some((read_phone_number(&digit_parts(text, '-')?)?, false));
+        }
+
+        let text = remove_thousands_separators(text)?;
+        let (integer, fraction) = match text.split_once('.') {
+            Some((integer, fraction)) => (integer, Some(fraction)),
+            None => (&*text, None),
+        };
+        let integer_reading = read_integer(integer)?;
+        match (fraction, counter) {
+            (Some(fraction), _) => {
+                if fraction.is_empty() || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+                    return None;
+                }
+                let mut reading = geminate(&integer_reading) + "テン";
+                reading.extend(fraction.bytes().map(|b| DIGITS[usize::from(b - b'0')]));
+                match counter {
+                    Some(counter) => Some((reading + &counter.reading, true)),
+                    None => Some((reading, false)),
+                }
+            }
+            (None, Some(counter)) => {
+                Some((counter.read(integer.parse().ok()?, &integer_reading), true))
+            }
+            (None, None) => Some((integer_reading, false)),
+        }
+    }
+
+    fn read_with(&self, counter: &str, digits: &str) -> Option<String> {
+        let number = digits.parse::<u128>().ok()?;
+        let reading = read_integer(&number.to_string())?;
+        Some(match self.find_counter(counter) {
+            Some(counter) => counter.read(number, &reading),
+            None => reading,
+        })
+    }
+
+    fn read_date(&self, year: &str, month: &str, day: &str) -> Option<String> {
+        let valid = |s: &str, range: std::ops::RangeInclusive<u32>| {
+            s.parse().is_ok_and(|n| range.contains(&n))
+        };
+        if !(year.len() <= 4 && valid(month, 1..=12) && valid(day, 1..=31)) {
+            return None;
+        }
+        Some(
+            self.read_with("年", year)?
+                + &self.read_with("月", month)?
+                + &self.read_with("日", day)?,
+        )
+    }
+
+    fn read_time(&self, parts: &[&str]) -> Option<String> {
+        let (hour, rest) = parts.split_first()?;
+        if !(1..=2).contains(&hour.len()) || !hour.parse::<u32>().is_ok_and(|h| h <= 24) {
+            return None;
+        }
+        if !(1..=2).contains(&rest.len()) || rest.iter().any(|s| s.len() != 2 || *s >= "60") {
+            return None;
+        }
+        let mut reading = self.read_with("時", hour)?;
+        for (part, counter) in rest.iter().zip(["分", "秒"]) {
+            if part.bytes().any(|b| b != b'0') {
+                reading += &self.read_with(counter, part)?;
+            }
+        }
+        Some(reading)
+    }
+}
+
+/// `start`から始まる、数字と記号のみからなるノードの並びの終わりを返す。並びは数字で始まり数字で終わる。
+fn number_run(nodes: &[NjdNode], start: usize) -> Option<usize> {
+    let is_number_part = |node: &NjdNode| {
+        !node.string.is_empty() && node.string.chars().all(|c| normalize(c).is_some())
+    };
+    let starts_with_digit = |node: &NjdNode| {
+        node.string
+            .chars()
+            .next()
+            .and_then(normalize)
+            .is_some_and(|c| c.is_ascii_digit())
+    };
+    let ends_with_digit = |node: &NjdNode| {
+        node.string
+            .chars()
+            .last()
+            .and_then(normalize)
+            .is_some_and(|c| c.is_ascii_digit())
+    };
+    if !starts_with_digit(nodes.get(start)?) || !is_number_part(&nodes[start]) {
+        return None;
+    }
+    let mut end = start
+        + nodes[start..]
+            .iter()
+            .take_while(|node| is_number_part(node))
+            .count();
+    while !ends_with_digit(&nodes[end - 1]) {
+        end -= 1;
+    }
+    Some(end)
+}
+
+/// 数字と、数の中に現れる記号を半角にする。それ以外は`None`。
+fn normalize(c: char) -> Option<char> {
+    match c {
+        '0'..='9' | ',' | '.' | '/' | ':' | '-' => Some(c),
+        '０'..='９' => char::from_u32(u32::from(c) - u32::from('０') + u32::from('0')),
+        '，' => Some(','),
+        '．' => Some('.'),
+        '／' => Some('/'),
+        '：' => Some(':'),
+        '－' => Some('-'),
+        _ => None,
+    }
+}
+
+fn number_node(base: &NjdNode, string: String, reading: String) -> NjdNode {
+    NjdNode {
+        orig: string.clone(),
+        string,
+        mora_size: split_moras(&reading).len() as i32,
+        read: reading.clone(),
+        pron: reading,
+        acc: 0,
+        ..base.clone()
+    }
+}
+
+/// `separator`で区切られた数字の列。1つでも数字以外を含めば`None`。
+fn digit_parts(text: &str, separator: char) -> Option<Vec<&str>> {
+    let parts = text.split(separator).collect::<Vec<_>>();
+    parts
+        .iter()
+        .all(|part| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit()))
+        .then_some(parts)
+}
+
+/// `1,000`のような3桁ごとの区切りを取り除く。区切り方がおかしければ`None`。
+fn remove_thousands_separators(text: &str) -> Option<String> {
+    let (integer, fraction) = match text.split_once('.') {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (text, None),
+    };
+    if fraction.is_some_and(|f| f.contains(',')) {
+        return None;
+    }
+    if integer.contains(',') {
+        let mut groups = integer.split(',');
+        let first = groups.next()?;
+        if !(1..=3).contains(&first.len()) || first.starts_with('0') || groups.any(|g| g.len() != 3)
+        {
+            return None;
+        }
+    }
+    let mut text = integer.replace(',', "");
+    if let Some(fraction) = fraction {
+        text.push('.');
+        text.push_str(fraction);
+    }
+    Some(text)
+}
+
+fn read_phone_number(parts: &[&str]) -> Option<String> {
+    if parts.len() < 2 || (parts.len() == 2 && !parts[0].starts_with('0')) {
+        return None;
+    }
+    Some(
+        parts
+            .iter()
+            .map(|part| {
+                part.bytes()
+                    .map(|b| PHONE_DIGITS[usize::from(b - b'0')])
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("ノ"),
+    )
+}
+
+/// 後ろに「テン」などが続くときの促音化。
+fn geminate(reading: &str) -> String {
+    for (from, to) in [
+        ("イチ", "イッ"),
+        ("ロク", "ロッ"),
+        ("ハチ", "ハッ"),
+        ("ジュー", "ジュッ"),
+        ("ヒャク", "ヒャッ"),
+    ] {
+        if let Some(stem) = reading.strip_suffix(from) {
+            return format!("{stem}{to}");
+        }
+    }
+    reading.to_owned()
+}
+
+/// 整数(半角数字の並び)を読む。0で始まる2桁以上の数と21桁以上の数は1桁ずつ読む。
+pub(crate) fn read_integer(digits: &str) -> Option<String> {
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    if (digits.len() > 1 && digits.starts_with('0')) || digits.len() > 4 * GROUP_UNITS.len() {
+        return Some(
+            digits
+                .bytes()
+                .map(|b| DIGITS[usize::from(b - b'0')])
+                .collect(),
+        );
+    }
+    let mut number = digits.parse::<u128>().ok()?;
+    if number == 0 {
+        return Some(DIGITS[0].to_owned());
+    }
+    let mut groups = vec![];
+    for unit in GROUP_UNITS {
+        groups.push(((number % 10000) as u32, unit));
+        number /= 10000;
+    }
+    Some(
+        groups
+            .into_iter()
+            .rev()
+            .filter(|&(group, _)| group > 0)
+            .map(|(group, unit)| {
+                let reading = read_under_10000(group, !unit.is_empty());
+                let reading = match unit {
+                    "チョー" => geminate_before(&reading, &["イチ", "ハチ", "ジュー"]),
+                    "ケー" => geminate(&reading),
+                    _ => reading,
+                };
+                reading + unit
+            })
+            .collect(),
+    )
+}
+
+fn geminate_before(reading: &str, targets: &[&str]) -> String {
+    if targets.iter().any(|target| reading.ends_with(target)) {
+        geminate(reading)
+    } else {
+        reading.to_owned()
+    }
+}
+
+/// 1以上9999以下の数を読む。`has_unit`は後ろに「マン」などが続くかどうか。
+fn read_under_10000(n: u32, has_unit: bool) -> String {
+    let (thousands, hundreds, tens, ones) = (n / 1000, n / 100 % 10, n / 10 % 10, n % 10);
+    let mut reading = String::new();
+    match thousands {
+        0 => {}
+        1 if has_unit && n == 1000 => reading += "イッセン",
+        1 => reading += "セン",
+        3 => reading += "サンゼン",
+        8 => reading += "ハッセン",
+        d => reading += &format!("{}セン", DIGITS[d as usize]),
+    }
+    match hundreds {
+        0 => {}
+        1 => reading += "ヒャク",
+        3 => reading += "サンビャク",
+        6 => reading += "ロッピャク",
+        8 => reading += "ハッピャク",
+        d => reading += &format!("{}ヒャク", DIGITS[d as usize]),
+    }
+    if tens > 0 {
+        if tens > 1 {
+            reading += DIGITS[tens as usize];
+        }
+        reading += "ジュー";
+    }
+    if ones > 0 {
+        reading += DIGITS[ones as usize];
+    }
+    reading
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[rstest]
+    #[case("0", "ゼロ")]
+    #[case("7", "ナナ")]
+    #[case("10", "ジュー")]
+    #[case("11", "ジューイチ")]
+    #[case("123", "ヒャクニジューサン")]
+    #[case("300", "サンビャク")]
+    #[case("600", "ロッピャク")]
+    #[case("800", "ハッピャク")]
+    #[case("1000", "セン")]
+    #[case("3000", "サンゼン")]
+    #[case("8000", "ハッセン")]
+    #[case("10000", "イチマン")]
+    #[case("10000000", "イッセンマン")]
+    #[case("120000000", "イチオクニセンマン")]
+    #[case("1000000000000", "イッチョー")]
+    #[case("8000000000000", "ハッチョー")]
+    #[case("10000000000000", "ジュッチョー")]
+    #[case("007", "ゼロゼロナナ")]
+    fn read_integer_works(#[case] digits: &str, #[case] expected: &str) {
+        assert_eq!(Some(expected.to_owned()), read_integer(digits));
+    }
+
+    /// MeCabと同様に、数字と記号を1文字ずつのノードにする。
+    fn nodes(text: &str, words: &[&str]) -> Vec<NjdNode> {
+        let mut nodes = vec![];
+        let mut rest = text;
+        while let Some(c) = rest.chars().next() {
+            let word = words
+                .iter()
+                .find(|word| rest.starts_with(**word))
+                .map_or(&rest[..c.len_utf8()], |word| word);
+            nodes.push(NjdNode {
+                string: word.to_owned(),
+                pos: "名詞".to_owned(),
+                pos_group1: if normalize(c).is_some() {
+                    "数"
+                } else {
+                    "一般"
+                }
+                .to_owned(),
+                pron: word.to_owned(),
+                chain_flag: -1,
+                ..Default::default()
+            });
+            rest = &rest[word.len()..];
+        }
+        nodes
+    }
+
+    fn prons(nodes: &[NjdNode]) -> Vec<(&str, &str)> {
+        nodes
+            .iter()
+            .map(|node| (&*node.string, &*node.pron))
+            .collect()
+    }
+
+    #[rstest]
+    #[case("３本", &[("３本", "サンボン")])]
+    #[case("２１本", &[("２１本", "ニジューイッポン")])]
+    #[case("１００本", &[("１００本", "ヒャッポン")])]
+    #[case("６個", &[("６個", "ロッコ")])]
+    #[case("１人", &[("１人", "ヒトリ")])]
+    #[case("１４人", &[("１４人", "ジューヨニン")])]
+    #[case("２０歳", &[("２０歳", "ハタチ")])]
+    #[case("３．１４", &[("３．１４", "サンテンイチヨン")])]
+    #[case("１，０００円", &[("１，０００円", "センエン")])]
+    #[case("＄５", &[("＄５", "ゴドル")])]
+    #[case("５０％", &[("５０％", "ゴジュッパーセント")])]
+    #[case("３／４", &[("３／４", "ヨンブンノサン")])]
+    #[case("１０／１９", &[("１０／１９", "ジューキューブンノジュー")])]
+    #[case(
+        "２０２４／１０／１９",
+        &[("２０２４／１０／１９", "ニセンニジューヨネンジューガツジュークニチ")]
+    )]
+    #[case("１２：３０", &[("１２：３０", "ジューニジサンジュップン")])]
+    #[case("９：００", &[("９：００", "クジ")])]
+    #[case(
+        "９９：００",
+        &[("９", "キュー"), ("９", "キュー"), ("：", "："), ("０", "ゼロ"), ("０", "ゼロ")]
+    )]
+    #[case(
+        "０３－１２３４－５６７８",
+        &[("０３－１２３４－５６７８", "ゼロサンノイチニーサンヨンノゴーロクナナハチ")]
+    )]
+    #[case("１，２", &[("１", "イチ"), ("，", "，"), ("２", "ニ")])]
+    #[case("第３", &[("第", "第"), ("３", "サン")])]
+    #[case("３．", &[("３", "サン"), ("．", "．")])]
+    fn number_reader_rewrite_works(#[case] text: &str, #[case] expected: &[(&str, &str)]) {
+        let words = ["本", "個", "人", "歳", "円", "ドル", "％"];
+        let rewritten = NumberReader::default().rewrite(nodes(text, &words));
+        assert_eq!(expected, prons(&rewritten));
+    }
+
+    #[rstest]
+    fn number_reader_counter_can_be_extended() {
+        let reader = NumberReader::default()
+            .counter(Counter::new("粒", "ツブ").exact(1, "ヒトツブ"))
+            .counter(Counter::new("本", "ホン"));
+        let rewritten = reader.rewrite(nodes("１粒３本", &[]));
+        assert_eq!(
+            vec![("１粒", "ヒトツブ"), ("３本", "サンホン")],
+            prons(&rewritten)
+        );
+        assert_eq!(4, rewritten[0].mora_size);
+        assert_eq!(0, rewritten[0].acc);
+    }
+
+    #[rstest]
+    fn number_reader_replaces_set_digit() {
+        let reader = NumberReader::default();
+        let pipeline = Pipeline::builder()
+            .replace(Stage::SetDigit, move |njd| reader.apply(njd))
+            .build()
+            .unwrap();
+        let mut open_jtalk = OpenJtalk::new(
+            camino::Utf8Path::new(std::env!("CARGO_MANIFEST_DIR"))
+                .join("src/mecab/testdata/mecab_load"),
+        )
+        .unwrap()
+        .with_pipeline(pipeline);
+        let phonemes = open_jtalk
+            .extract_fullcontext("3本")
+            .unwrap()
+            .iter()
+            .map(|label| label.parse::<FullContextLabel>().unwrap())
+            .filter_map(|label| label.phoneme().map(ToOwned::to_owned))
+            .collect::<Vec<_>>();
+        assert_eq!(["sil", "s", "a", "N", "b", "o", "N", "sil"], phonemes[..]);
+    }
+
+    /// `text`を解析し、`set_digit`の段階の後の(読み, モーラ数)を返す。
+    /// `reader`が`Some`なら`njd_set_digit`の代わりに使う。
+    fn after_set_digit(text: &str, reader: Option<NumberReader>) -> (String, i32) {
+        let captured = std::sync::Arc::new(std::sync::Mutex::new(vec![]));
+        let mut builder = Pipeline::builder();
+        if let Some(reader) = reader {
+            builder = builder.replace(Stage::SetDigit, move |njd| reader.apply(njd));
+        }
+        let pipeline = builder
+            .hook_after(Stage::SetDigit, {
+                let captured = captured.clone();
+                move |njd| {
+                    *captured.lock().unwrap() = njd.nodes()?;
+                    Ok(())
+                }
+            })
+            .build()
+            .unwrap();
+        OpenJtalk::new(
+            camino::Utf8Path::new(std::env!("CARGO_MANIFEST_DIR"))
+                .join("src/mecab/testdata/mecab_load"),
+        )
+        .unwrap()
+        .with_pipeline(pipeline)
+        .extract_fullcontext(text)
+        .unwrap();
+        let nodes = captured.lock().unwrap();
+        (
+            nodes.iter().map(|node| &*node.pron).collect(),
+            nodes.iter().map(|node| node.mora_size).sum(),
+        )
+    }
+
+    /// `njd_set_digit`との比較。`expected`が`Some`のものは意図的に読みを変えており、その読みになる。
+    #[rstest]
+    #[case("7", None)]
+    #[case("123", None)]
+    #[case("1000", None)]
+    #[case("10000", None)]
+    #[case("１，０００", None)]
+    #[case("3本", None)]
+    // 分数として読む(月日としては読まない)
+    #[case("10/19", Some("ジューキューブンノジュー"))]
+    // 時刻として読む
+    #[case("12:30", Some("ジューニジサンジュップン"))]
+    // 数全体に対する助数詞の読み
+    #[case("20歳", Some("ハタチ"))]
+    // 数の前の記号を助数詞として読む
+    #[case("＄5", Some("ゴドル"))]
+    fn number_reader_is_compatible_with_set_digit(
+        #[case] text: &str,
+        #[case] expected: Option<&str>,
+    ) {
+        let c = after_set_digit(text, None);
+        let rust = after_set_digit(text, Some(NumberReader::default()));
+        match expected {
+            None => assert_eq!(c, rust),
+            Some(expected) => {
+                assert_eq!(expected, rust.0);
+                assert_ne!(c.0, rust.0);
+            }
+        }
+    }
+}
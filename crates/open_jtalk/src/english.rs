@@ -0,0 +1,508 @@
+use std::collections::HashMap;
+
+use super::*;
+
+const LETTERS: [&str; 26] = [
+    "エー",
+    "ビー",
+    "シー",
+    "ディー",
+    "イー",
+    "エフ",
+    "ジー",
+    "エイチ",
+    "アイ",
+    "ジェー",
+    "ケー",
+    "エル",
+    "エム",
+    "エヌ",
+    "オー",
+    "ピー",
+    "キュー",
+    "アール",
+    "エス",
+    "ティー",
+    "ユー",
+    "ブイ",
+    "ダブリュー",
+    "エックス",
+    "ワイ",
+    "ゼット",
+];
+
+/// 同梱の英単語の読み。(綴り, 読み, アクセント型)
+const LEXICON: &[(&str, &str, usize)] = &[
+    ("ai", "エーアイ", 3),
+    ("amazon", "アマゾン", 2),
+    ("android", "アンドロイド", 3),
+    ("apple", "アップル", 1),
+    ("chrome", "クローム", 2),
+    ("github", "ギットハブ", 4),
+    ("google", "グーグル", 1),
+    ("hello", "ハロー", 1),
+    ("ipad", "アイパッド", 3),
+    ("iphone", "アイフォーン", 3),
+    ("linux", "リナックス", 2),
+    ("microsoft", "マイクロソフト", 5),
+    ("open", "オープン", 1),
+    ("parser", "パーサー", 1),
+    ("python", "パイソン", 1),
+    ("rust", "ラスト", 1),
+    ("station", "ステーション", 2),
+    ("the", "ザ", 1),
+    ("twitter", "ツイッター", 1),
+    ("voicevox", "ボイスボックス", 4),
+    ("windows", "ウィンドウズ", 1),
+    ("world", "ワールド", 1),
+    ("youtube", "ユーチューブ", 3),
+];
+
+/// アルファベットの並びを英単語として読むNJDの処理。`mecab2njd`の後に呼ぶ。
+///
+/// 辞書(同梱のものと、[`EnglishReader::word`]などで追加したもの)にない単語は、
+/// 大文字のみの略語なら1文字ずつ、それ以外は綴りから推測して読む。`iPhoneApp`のような
+/// キャメルケースは単語に分けて読む。
+///
+/// ```
+/// use open_jtalk::{EnglishReader, Pipeline, Stage};
+///
+/// let reader = EnglishReader::default().word("VOICEVOX", "ボイスボックス", 4);
+/// let pipeline = Pipeline::builder()
+///     .hook_after(Stage::Mecab2Njd, move |njd| reader.apply(njd))
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct EnglishReader {
+    /// 小文字にした綴りから(読み, アクセント型)へ。
+    lexicon: HashMap<String, (String, usize)>,
+}
+
+impl Default for EnglishReader {
+    fn default() -> Self {
+        LEXICON
+            .iter()
+            .fold(Self::empty(), |reader, &(spelling, reading, accent)| {
+                reader.word(spelling, reading, accent)
+            })
+    }
+}
+
+impl EnglishReader {
+    /// 同梱の辞書を使わないもの。
+    pub fn empty() -> Self {
+        Self {
+            lexicon: HashMap::new(),
+        }
+    }
+
+    /// 単語を辞書に追加する。綴りの大文字と小文字は区別しない。
+    pub fn word(mut self, spelling: &str, reading: impl Into<String>, accent: usize) -> Self {
+        self.lexicon
+            .insert(spelling.to_ascii_lowercase(), (reading.into(), accent));
+        self
+    }
+
+    /// `綴り,読み,アクセント型`の形式のCSVを辞書に追加する。空行と`#`で始まる行は無視する。
+    pub fn load_lexicon(self, csv: &str) -> Result<Self> {
+        csv.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .try_fold(self, |reader, line| {
+                let error = || Error::Parse {
+                    what: "English lexicon entry",
+                    input: line.to_owned(),
+                };
+                let mut fields = line.split(',');
+                let (Some(spelling), Some(reading), Some(accent), None) =
+                    (fields.next(), fields.next(), fields.next(), fields.next())
+                else {
+                    return Err(error());
+                };
+                if spelling.is_empty() || !spelling.bytes().all(|b| b.is_ascii_alphabetic()) {
+                    return Err(error());
+                }
+                let accent = accent.parse().map_err(|_| error())?;
+                Ok(reader.word(spelling, reading, accent))
+            })
+    }
+
+    /// ASCIIのアルファベットのみからなる単語の読みとアクセント型を返す。
+    pub fn read(&self, word: &str) -> Option<(String, usize)> {
+        if word.is_empty() || !word.bytes().all(|b| b.is_ascii_alphabetic()) {
+            return None;
+        }
+        if let Some(entry) = self.lexicon.get(&word.to_ascii_lowercase()) {
+            return Some(entry.clone());
+        }
+        let reading = split_camel_case(word)
+            .into_iter()
+            .map(|part| match self.lexicon.get(&part.to_ascii_lowercase()) {
+                Some((reading, _)) => reading.clone(),
+                None if is_acronym(part) => spell_out(part),
+                None => transliterate(&part.to_ascii_lowercase()),
+            })
+            .collect::<String>();
+        let accent = loanword_accent(&reading);
+        Some((reading, accent))
+    }
+
+    pub fn apply(&self, njd: &mut Njd) -> Result<()> {
+        let nodes = self.rewrite(njd.nodes()?);
+        njd.set_nodes(&nodes)
+    }
+
+    /// 連続するアルファベットのみのノードを1つにまとめ、読みを与える。
+    pub(crate) fn rewrite(&self, nodes: Vec<NjdNode>) -> Vec<NjdNode> {
+        let mut result = Vec::<NjdNode>::with_capacity(nodes.len());
+        let mut i = 0;
+        while i < nodes.len() {
+            let end = i + nodes[i..]
+                .iter()
+                .take_while(|node| to_ascii_word(&node.string).is_some())
+                .count();
+            if end == i {
+                result.push(nodes[i].clone());
+                i += 1;
+                continue;
+            }
+            let string = nodes[i..end]
+                .iter()
+                .map(|node| &*node.string)
+                .collect::<String>();
+            let word = to_ascii_word(&string).expect("each node is alphabetic");
+            let (reading, accent) = self.read(&word).expect("`word` is alphabetic");
+            result.push(NjdNode {
+                orig: string.clone(),
+                string,
+                pos: "名詞".to_owned(),
+                pos_group1: "固有名詞".to_owned(),
+                pos_group2: "一般".to_owned(),
+                pos_group3: "*".to_owned(),
+                ctype: "*".to_owned(),
+                cform: "*".to_owned(),
+                mora_size: split_moras(&reading).len() as i32,
+                read: reading.clone(),
+                pron: reading,
+                acc: accent as i32,
+                chain_rule: "*".to_owned(),
+                ..nodes[i].clone()
+            });
+            i = end;
+        }
+        result
+    }
+}
+
+/// 全角のアルファベットを半角にする。アルファベット以外を含めば`None`。
+fn to_ascii_word(s: &str) -> Option<String> {
+    if s.is_empty() {
+        return None;
+    }
+    s.chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' => Some(c),
+            'ａ'..='ｚ' | 'Ａ'..='Ｚ' => char::from_u32(u32::from(c) - 0xfee0),
+            _ => None,
+        })
+        .collect()
+}
+
+/// `iPhoneApp`を`i`、`Phone`、`App`に、`XMLParser`を`XML`、`Parser`に分ける。
+fn split_camel_case(word: &str) -> Vec<&str> {
+    let bytes = word.as_bytes();
+    let mut parts = vec![];
+    let mut start = 0;
+    for i in 1..bytes.len() {
+        let lower_to_upper = bytes[i - 1].is_ascii_lowercase() && bytes[i].is_ascii_uppercase();
+        let acronym_end = bytes[i - 1].is_ascii_uppercase()
+            && bytes[i].is_ascii_uppercase()
+            && bytes.get(i + 1).is_some_and(u8::is_ascii_lowercase);
+        if lower_to_upper || acronym_end {
+            parts.push(&word[start..i]);
+            start = i;
+        }
+    }
+    parts.push(&word[start..]);
+    parts
+}
+
+/// 1文字のもの、2文字以上の大文字のみのもの、母音を含まないものは1文字ずつ読む。
+fn is_acronym(word: &str) -> bool {
+    word.len() == 1
+        || word.bytes().all(|b| b.is_ascii_uppercase())
+        || !word
+            .bytes()
+            .any(|b| b"aeiouy".contains(&b.to_ascii_lowercase()))
+}
+
+fn spell_out(word: &str) -> String {
+    word.bytes()
+        .map(|b| LETTERS[usize::from(b.to_ascii_lowercase() - b'a')])
+        .collect()
+}
+
+/// 外来語のアクセント規則(後ろから3モーラ目)による、アクセント型。
+/// 特殊拍(`ッ`、`ン`、`ー`)に当たる場合は1つ前にずらす。
+fn loanword_accent(reading: &str) -> usize {
+    let moras = split_moras(reading);
+    if moras.len() <= 2 {
+        return 1;
+    }
+    let mut accent = moras.len() - 2;
+    while accent > 1 && matches!(moras[accent - 1], "ッ" | "ン" | "ー") {
+        accent -= 1;
+    }
+    accent
+}
+
+/// 小文字の綴りから、ローマ字読みに近い規則で読みを推測する。
+fn transliterate(word: &str) -> String {
+    let mut word = word.to_owned();
+    if word.len() > 3 {
+        if let Some(stem) = word.strip_suffix("le") {
+            word = format!("{stem}ru");
+        } else if let Some(stem) = ["er", "or", "ar"]
+            .iter()
+            .find_map(|suffix| word.strip_suffix(suffix))
+        {
+            word = format!("{stem}aa");
+        } else if word.ends_with('e') && !is_vowel(word.as_bytes()[word.len() - 2]) {
+            word.pop();
+        }
+    }
+    for (from, to) in [
+        ("tion", "shon"),
+        ("sion", "jon"),
+        ("ght", "t"),
+        ("ph", "f"),
+        ("th", "s"),
+        ("wh", "w"),
+        ("ck", "kk"),
+        ("qu", "kw"),
+        ("x", "ks"),
+        ("ee", "ii"),
+        ("ea", "ii"),
+        ("oo", "uu"),
+        ("ou", "au"),
+        ("ai", "ei"),
+        ("ay", "ei"),
+    ] {
+        word = word.replace(from, to);
+    }
+    to_kana(word.as_bytes())
+}
+
+fn is_vowel(b: u8) -> bool {
+    b"aeiou".contains(&b)
+}
+
+fn to_kana(word: &[u8]) -> String {
+    let mut kana = String::new();
+    let mut previous_vowel = None;
+    let mut i = 0;
+    while i < word.len() {
+        let next = word.get(i + 1).copied();
+        // 母音の前にない`y`は`i`として読む
+        let c = match word[i] {
+            b'y' if !next.is_some_and(is_vowel) => b'i',
+            c => c,
+        };
+        if is_vowel(c) {
+            if previous_vowel == Some(c) {
+                kana.push('ー');
+            } else {
+                kana += vowel_kana("", c);
+            }
+            previous_vowel = Some(c);
+            i += 1;
+            continue;
+        }
+
+        let (consonant, len) = match (c, next) {
+            (b's', Some(b'h')) => ("sh", 2),
+            (b'c', Some(b'h')) => ("ch", 2),
+            (b'k', Some(b'w')) => ("kw", 2),
+            (b'c', Some(b'e' | b'i' | b'y')) => ("s", 1),
+            (b'c' | b'k' | b'q', _) => ("k", 1),
+            (b'l' | b'r', _) => ("r", 1),
+            (b'v' | b'b', _) => ("b", 1),
+            _ => (std::str::from_utf8(&word[i..=i]).unwrap_or(""), 1),
+        };
+        i += len;
+        previous_vowel = None;
+        match word.get(i).copied() {
+            Some(v) if is_vowel(v) => {
+                kana += vowel_kana(consonant, v);
+                previous_vowel = Some(v);
+                i += 1;
+            }
+            _ if consonant == "n" => kana.push('ン'),
+            Some(b'b' | b'p' | b'm') if consonant == "m" => kana.push('ン'),
+            Some(n) if n == c => kana.push('ッ'),
+            _ => kana += lone_consonant_kana(consonant),
+        }
+    }
+    kana
+}
+
+fn vowel_kana(consonant: &str, vowel: u8) -> &'static str {
+    let row: [&str; 5] = match consonant {
+        "" => ["ア", "イ", "ウ", "エ", "オ"],
+        "k" => ["カ", "キ", "ク", "ケ", "コ"],
+        "g" => ["ガ", "ギ", "グ", "ゲ", "ゴ"],
+        "s" => ["サ", "シ", "ス", "セ", "ソ"],
+        "z" => ["ザ", "ジ", "ズ", "ゼ", "ゾ"],
+        "t" => ["タ", "ティ", "トゥ", "テ", "ト"],
+        "d" => ["ダ", "ディ", "ドゥ", "デ", "ド"],
+        "n" => ["ナ", "ニ", "ヌ", "ネ", "ノ"],
+        "h" => ["ハ", "ヒ", "フ", "ヘ", "ホ"],
+        "b" => ["バ", "ビ", "ブ", "ベ", "ボ"],
+        "p" => ["パ", "ピ", "プ", "ペ", "ポ"],
+        "m" => ["マ", "ミ", "ム", "メ", "モ"],
+        "y" => ["ヤ", "イ", "ユ", "イェ", "ヨ"],
+        "r" => ["ラ", "リ", "ル", "レ", "ロ"],
+        "w" => ["ワ", "ウィ", "ウ", "ウェ", "ウォ"],
+        "f" => ["ファ", "フィ", "フ", "フェ", "フォ"],
+        "j" => ["ジャ", "ジ", "ジュ", "ジェ", "ジョ"],
+        "sh" => ["シャ", "シ", "シュ", "シェ", "ショ"],
+        "ch" => ["チャ", "チ", "チュ", "チェ", "チョ"],
+        "kw" => ["クァ", "クィ", "ク", "クェ", "クォ"],
+        _ => return "",
+    };
+    let index = match vowel {
+        b'a' => 0,
+        b'i' => 1,
+        b'u' => 2,
+        b'e' => 3,
+        _ => 4,
+    };
+    row[index]
+}
+
+fn lone_consonant_kana(consonant: &str) -> &'static str {
+    match consonant {
+        "k" | "kw" => "ク",
+        "g" => "グ",
+        "s" => "ス",
+        "z" => "ズ",
+        "t" => "ト",
+        "d" => "ド",
+        "b" => "ブ",
+        "p" => "プ",
+        "m" => "ム",
+        "r" => "ル",
+        "w" => "ウ",
+        "f" => "フ",
+        "j" => "ジ",
+        "sh" => "シュ",
+        "ch" => "チ",
+        _ => "",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    // 綴りからの推測は、`docker`や`phone`のように綴りどおりに読む単語でしか正しくない。
+    // `station`(スタション)や`ipad`(アイパド)のように誤るものは`LEXICON`に載せている。
+    #[rstest]
+    #[case("Google", "グーグル", 1)]
+    #[case("GOOGLE", "グーグル", 1)]
+    #[case("apple", "アップル", 1)]
+    #[case("docker", "ドッカー", 1)]
+    #[case("phone", "フォン", 1)]
+    #[case("station", "ステーション", 2)]
+    #[case("NHK", "エヌエイチケー", 5)]
+    #[case("iPhoneApp", "アイフォンアップ", 5)]
+    #[case("iPad", "アイパッド", 3)]
+    #[case("XMLParser", "エックスエムエルパーサー", 9)]
+    #[case("YouTube", "ユーチューブ", 3)]
+    fn english_reader_read_works(#[case] word: &str, #[case] reading: &str, #[case] accent: usize) {
+        assert_eq!(
+            Some((reading.to_owned(), accent)),
+            EnglishReader::default().read(word)
+        );
+    }
+
+    #[rstest]
+    fn english_reader_read_rejects_non_alphabetic_words() {
+        let reader = EnglishReader::default();
+        assert_eq!(None, reader.read(""));
+        assert_eq!(None, reader.read("mp3"));
+    }
+
+    #[rstest]
+    fn english_reader_load_lexicon_works() {
+        let reader = EnglishReader::empty()
+            .load_lexicon("# 綴り,読み,アクセント型\n\nGoogle,ググル,2\n")
+            .unwrap();
+        assert_eq!(Some(("ググル".to_owned(), 2)), reader.read("google"));
+        assert!(matches!(
+            EnglishReader::empty().load_lexicon("Google,グーグル"),
+            Err(Error::Parse { .. })
+        ));
+        assert!(matches!(
+            EnglishReader::empty().load_lexicon("mp3,エムピースリー,4"),
+            Err(Error::Parse { .. })
+        ));
+    }
+
+    #[rstest]
+    fn english_reader_rewrite_works() {
+        let node = |string: &str, pos: &str| NjdNode {
+            string: string.to_owned(),
+            pos: pos.to_owned(),
+            pron: "*".to_owned(),
+            chain_flag: -1,
+            ..Default::default()
+        };
+        let rewritten = EnglishReader::default().rewrite(vec![
+            node("ｉ", "記号"),
+            node("Ｐｈｏｎｅ", "名詞"),
+            node("を", "助詞"),
+            node("Ａ", "記号"),
+        ]);
+        assert_eq!(
+            vec![
+                ("ｉＰｈｏｎｅ", "アイフォーン", 3, 5),
+                ("を", "*", 0, 0),
+                ("Ａ", "エー", 1, 2),
+            ],
+            rewritten
+                .iter()
+                .map(|node| (&*node.string, &*node.pron, node.acc, node.mora_size))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!("名詞", rewritten[2].pos);
+    }
+
+    #[rstest]
+    fn english_reader_runs_after_mecab2njd() {
+        let reader = EnglishReader::default();
+        let pipeline = Pipeline::builder()
+            .hook_after(Stage::Mecab2Njd, move |njd| reader.apply(njd))
+            .build()
+            .unwrap();
+        let mut open_jtalk = OpenJtalk::new(
+            camino::Utf8Path::new(std::env!("CARGO_MANIFEST_DIR"))
+                .join("src/mecab/testdata/mecab_load"),
+        )
+        .unwrap()
+        .with_pipeline(pipeline);
+        let phonemes = open_jtalk
+            .extract_fullcontext("Google")
+            .unwrap()
+            .iter()
+            .map(|label| label.parse::<FullContextLabel>().unwrap())
+            .filter_map(|label| label.phoneme().map(ToOwned::to_owned))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ["sil", "g", "u", "u", "g", "u", "r", "u", "sil"],
+            phonemes[..]
+        );
+    }
+}
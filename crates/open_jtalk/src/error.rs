@@ -0,0 +1,51 @@
+use super::*;
+
+#[derive(thiserror::Error, Clone, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    MecabLoad(#[from] MecabLoadError),
+    #[error(transparent)]
+    Text2Mecab(#[from] Text2MecabError),
+    #[error(transparent)]
+    Kana(#[from] KanaParseError),
+    #[error("{resource} is not initialized")]
+    Uninitialized { resource: &'static str },
+    #[error("failed to initialize {resource}")]
+    Initialize { resource: &'static str },
+    #[error("`{function}` failed")]
+    Unsuccessful { function: &'static str },
+    #[error("string contained a NUL byte at byte {position}")]
+    Nul { position: usize },
+    #[error("no features were produced")]
+    NoFeatures,
+    #[error("string was not valid UTF-8")]
+    InvalidUtf8(#[from] std::str::Utf8Error),
+    #[error("failed to parse {what}: {input:?}")]
+    Parse { what: &'static str, input: String },
+    #[error("invalid pipeline: {reason}")]
+    InvalidPipeline { reason: String },
+    #[error("no node matched the reading override at bytes {start}..{end}")]
+    UnmatchedOverride { start: usize, end: usize },
+    #[error("the reading override at bytes {start}..{end} overlaps another one")]
+    OverlappingOverride { start: usize, end: usize },
+    #[error("cancelled")]
+    Cancelled,
+    #[error(transparent)]
+    Io(std::sync::Arc<std::io::Error>),
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e.into())
+    }
+}
+
+impl From<std::ffi::NulError> for Error {
+    fn from(e: std::ffi::NulError) -> Self {
+        Self::Nul {
+            position: e.nul_position(),
+        }
+    }
+}
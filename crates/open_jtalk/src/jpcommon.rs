@@ -1,100 +1,179 @@
 use super::*;
-use std::{ffi::CStr, mem::MaybeUninit, os::raw::c_char};
+use std::{ffi::CStr, fmt, os::raw::c_char};
 
-#[derive(Default)]
-pub struct JpCommon(Option<open_jtalk_sys::JPCommon>);
+/// NJDからフルコンテキストラベルを生成する。
+///
+/// C側の構造体はヒープ上に確保するので、値を移動しても構わない。
+/// `label`はC側が確保・解放する領域を指し、`refresh`や`Drop`で解放される。
+pub struct JpCommon(RawBox<open_jtalk_sys::JPCommon>);
 
-pub struct JpCommonLabelFeature;
+/// [`JpCommon::make_label`]で生成したフルコンテキストラベルの列。
+///
+/// `JpCommon`を借用しているので、保持している間は`JpCommon::refresh`などを呼べない。
+#[derive(Clone, Copy)]
+pub struct JpCommonLabelFeatures<'a>(&'a [*mut c_char]);
 
-pub struct JpCommonLabelFeatureIter<'a> {
-    label_features: &'a JpCommonLabelFeature,
-    index: i32,
-    size: i32,
-}
+pub struct JpCommonLabelFeatureIter<'a>(std::slice::Iter<'a, *mut c_char>);
 
 unsafe impl resources::Resource for JpCommon {
     unsafe fn initialize(&mut self) -> bool {
-        if self.0.is_some() {
-            panic!("already initialized jpcommon");
+        if self.0.is_initialized() {
+            return false;
         }
-        let jpcommon = {
-            let mut jpcommon = MaybeUninit::<open_jtalk_sys::JPCommon>::uninit();
-            open_jtalk_sys::JPCommon_initialize(jpcommon.as_mut_ptr());
-            jpcommon.assume_init()
-        };
-        self.0 = Some(jpcommon);
+        open_jtalk_sys::JPCommon_initialize(self.0.as_uninit_ptr());
+        self.0.set_initialized(true);
         true
     }
     unsafe fn clear(&mut self) -> bool {
-        open_jtalk_sys::JPCommon_clear(self.as_raw_ptr());
-        self.0 = None;
+        let Ok(jpcommon) = self.as_raw_ptr() else {
+            return false;
+        };
+        open_jtalk_sys::JPCommon_clear(jpcommon);
+        self.0.set_initialized(false);
         true
     }
 }
 
+impl Drop for JpCommon {
+    fn drop(&mut self) {
+        unsafe {
+            resources::Resource::clear(self);
+        }
+    }
+}
+
 // SAFETY: `Send`と対立する性質はないはず。
 unsafe impl Send for JpCommon {}
 
+impl fmt::Debug for JpCommon {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let labels = self
+            .get_label_features()
+            .ok()
+            .flatten()
+            .map(|labels| labels.iter().flatten().collect::<Vec<_>>());
+        f.debug_struct("JpCommon")
+            .field("nodes", &self.to_debug_string().ok())
+            .field("labels", &labels)
+            .finish()
+    }
+}
+
+impl<'a> JpCommonLabelFeatures<'a> {
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> JpCommonLabelFeatureIter<'a> {
+        JpCommonLabelFeatureIter(self.0.iter())
+    }
+}
+
+impl<'a> IntoIterator for JpCommonLabelFeatures<'a> {
+    type Item = Result<&'a str>;
+    type IntoIter = JpCommonLabelFeatureIter<'a>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
 impl<'a> Iterator for JpCommonLabelFeatureIter<'a> {
-    type Item = &'a str;
+    type Item = Result<&'a str>;
     fn next(&mut self) -> Option<Self::Item> {
-        let label_features_ptr =
-            self.label_features as *const JpCommonLabelFeature as *mut *mut c_char;
-        unsafe {
-            if self.index < self.size {
-                let label_feature = *label_features_ptr.offset(self.index as isize);
-                self.index += 1;
-                Some(CStr::from_ptr(label_feature).to_str().unwrap())
-            } else {
-                None
-            }
-        }
+        self.0.next().map(|&label_feature| {
+            unsafe { CStr::from_ptr(label_feature) }
+                .to_str()
+                .map_err(Into::into)
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
     }
 }
 
 impl JpCommon {
-    unsafe fn as_raw_ptr(&self) -> *mut open_jtalk_sys::JPCommon {
-        if self.0.is_none() {
-            panic!("uninitialized jpcommon");
+    pub fn new() -> Result<Self> {
+        let mut jpcommon = Self(RawBox::new());
+        if !unsafe { resources::Resource::initialize(&mut jpcommon) } {
+            return Err(Error::Initialize {
+                resource: "jpcommon",
+            });
         }
-        self.0.as_ref().unwrap() as *const open_jtalk_sys::JPCommon as *mut open_jtalk_sys::JPCommon
+        Ok(jpcommon)
     }
 
-    pub fn refresh(&mut self) {
-        unsafe { open_jtalk_sys::JPCommon_refresh(self.as_raw_ptr()) }
+    unsafe fn as_raw_ptr(&self) -> Result<*mut open_jtalk_sys::JPCommon> {
+        self.0.get().ok_or(Error::Uninitialized {
+            resource: "jpcommon",
+        })
     }
 
-    pub fn make_label(&mut self) {
-        unsafe { open_jtalk_sys::JPCommon_make_label(self.as_raw_ptr()) }
+    /// `JPCommon_fprint`が書き出す内容(ノードごとに`pron,pos,ctype,cform,acc,chain_flag`)を返す。
+    ///
+    /// `njd2jpcommon`の前は空になる。C側と同じく、NULLの文字列は`(null)`と書く。
+    pub fn to_debug_string(&self) -> Result<String> {
+        let field = |s: *const c_char| {
+            if s.is_null() {
+                Ok("(null)")
+            } else {
+                unsafe { CStr::from_ptr(s) }.to_str()
+            }
+        };
+        let mut output = String::new();
+        unsafe {
+            let mut node = (*self.as_raw_ptr()?).head;
+            while let Some(n) = node.as_ref() {
+                output += &format!(
+                    "{},{},{},{},{},{}\n",
+                    field(n.pron)?,
+                    field(n.pos)?,
+                    field(n.ctype)?,
+                    field(n.cform)?,
+                    n.acc,
+                    n.chain_flag,
+                );
+                node = n.next;
+            }
+        }
+        Ok(output)
     }
 
-    pub fn get_label_size(&self) -> i32 {
-        unsafe { open_jtalk_sys::JPCommon_get_label_size(self.as_raw_ptr()) }
+    pub fn refresh(&mut self) -> Result<()> {
+        unsafe { open_jtalk_sys::JPCommon_refresh(self.as_raw_ptr()?) }
+        Ok(())
     }
 
-    pub fn njd2jpcommon(&mut self, njd: &Njd) {
-        unsafe { open_jtalk_sys::njd2jpcommon(self.as_raw_ptr(), njd.as_raw_ptr()) }
+    pub fn make_label(&mut self) -> Result<()> {
+        unsafe { open_jtalk_sys::JPCommon_make_label(self.as_raw_ptr()?) }
+        Ok(())
     }
 
-    pub fn get_label_feature_to_iter(&self) -> Option<JpCommonLabelFeatureIter<'_>> {
-        self.get_label_feature_raw().map(|label_features| {
-            let label_features_size = self.get_label_size();
-            JpCommonLabelFeatureIter {
-                label_features,
-                index: 0,
-                size: label_features_size,
-            }
-        })
+    pub fn get_label_size(&self) -> Result<i32> {
+        unsafe { Ok(open_jtalk_sys::JPCommon_get_label_size(self.as_raw_ptr()?)) }
+    }
+
+    pub fn njd2jpcommon(&mut self, njd: &Njd) -> Result<()> {
+        unsafe { open_jtalk_sys::njd2jpcommon(self.as_raw_ptr()?, njd.as_raw_ptr()?) }
+        Ok(())
     }
 
-    pub(crate) fn get_label_feature_raw(&self) -> Option<&JpCommonLabelFeature> {
+    pub fn get_label_feature_to_iter(&self) -> Result<Option<JpCommonLabelFeatureIter<'_>>> {
+        Ok(self.get_label_features()?.map(|features| features.iter()))
+    }
+
+    pub fn get_label_features(&self) -> Result<Option<JpCommonLabelFeatures<'_>>> {
         unsafe {
-            let feature = open_jtalk_sys::JPCommon_get_label_feature(self.as_raw_ptr());
-            if !feature.is_null() {
-                Some(&*(feature as *const JpCommonLabelFeature))
-            } else {
-                None
-            }
+            let jpcommon = self.as_raw_ptr()?;
+            Ok(c_str_array(
+                open_jtalk_sys::JPCommon_get_label_feature(jpcommon),
+                open_jtalk_sys::JPCommon_get_label_size(jpcommon),
+            )
+            .map(JpCommonLabelFeatures))
         }
     }
 }
@@ -103,33 +182,99 @@ impl JpCommon {
 mod tests {
 
     use super::*;
+    use camino::Utf8Path;
     use pretty_assertions::assert_eq;
     use resources::Resource as _;
     #[rstest]
     fn jpcommon_initialize_and_clear_works() {
-        let mut jpcommon = JpCommon::default();
+        let mut jpcommon = JpCommon::new().unwrap();
         unsafe {
-            assert!(jpcommon.initialize());
+            assert!(!jpcommon.initialize());
             assert!(jpcommon.clear());
+            assert!(!jpcommon.clear());
+            assert!(jpcommon.initialize());
         }
     }
 
     #[rstest]
     fn jpcommon_get_label_size_before_make_labelworks() {
-        let jpcommon = ManagedResource::<JpCommon>::initialize();
-        assert_eq!(0, jpcommon.get_label_size());
+        let jpcommon = JpCommon::new().unwrap();
+        assert_eq!(0, jpcommon.get_label_size().unwrap());
+    }
+
+    #[rstest]
+    fn jpcommon_get_label_features_before_make_label_works() {
+        let jpcommon = JpCommon::new().unwrap();
+
+        assert!(jpcommon.get_label_features().unwrap().is_none());
+    }
+
+    #[rstest]
+    fn jpcommon_to_debug_string_works() {
+        let jpcommon = JpCommon::new().unwrap();
+        assert_eq!("", jpcommon.to_debug_string().unwrap());
+        assert_eq!(
+            r#"JpCommon { nodes: Some(""), labels: None }"#,
+            format!("{jpcommon:?}")
+        );
     }
 
     #[rstest]
-    fn jpcommon_get_label_feature_mut_before_make_label_works() {
-        let jpcommon = ManagedResource::<JpCommon>::initialize();
+    fn jpcommon_to_debug_string_dumps_nodes_after_njd2jpcommon() {
+        let mut mecab = Mecab::new().unwrap();
+        mecab
+            .load(
+                Utf8Path::new(std::env!("CARGO_MANIFEST_DIR"))
+                    .join("src/mecab/testdata/mecab_load"),
+            )
+            .unwrap();
+        mecab.analysis(text2mecab("こんにちは").unwrap()).unwrap();
+        let mut njd = Njd::new().unwrap();
+        njd.mecab2njd(mecab.get_feature().unwrap().unwrap())
+            .unwrap();
+        let mut jpcommon = JpCommon::new().unwrap();
+        jpcommon.njd2jpcommon(&njd).unwrap();
 
-        assert!(jpcommon.get_label_feature_raw().is_none());
+        let dump = jpcommon.to_debug_string().unwrap();
+        assert_eq!(njd.nodes().unwrap().len(), dump.lines().count());
+        assert!(dump.starts_with("コンニチワ,"));
+        assert!(dump.lines().all(|line| line.split(',').count() == 6));
+        assert!(jpcommon.get_label_features().unwrap().is_none());
     }
 
     #[rstest]
     fn jpcommon_refresh_works() {
-        let mut jpcommon = ManagedResource::<JpCommon>::initialize();
-        jpcommon.refresh();
+        let mut jpcommon = JpCommon::new().unwrap();
+        jpcommon.refresh().unwrap();
+    }
+
+    #[rstest]
+    fn jpcommon_can_be_moved_across_threads() {
+        let jpcommon = JpCommon::new().unwrap();
+        let ptr = unsafe { jpcommon.as_raw_ptr().unwrap() };
+        let jpcommons = vec![jpcommon];
+        let jpcommon = std::thread::spawn(move || {
+            let mut jpcommon = jpcommons.into_iter().next().unwrap();
+            jpcommon.make_label().unwrap();
+            jpcommon
+        })
+        .join()
+        .unwrap();
+        assert_eq!(ptr, unsafe { jpcommon.as_raw_ptr().unwrap() });
+        assert_eq!(0, jpcommon.get_label_size().unwrap());
+    }
+
+    #[rstest]
+    fn jpcommon_cleared_returns_error() {
+        let mut jpcommon = JpCommon::new().unwrap();
+        unsafe {
+            jpcommon.clear();
+        }
+        assert!(matches!(
+            jpcommon.get_label_size(),
+            Err(Error::Uninitialized {
+                resource: "jpcommon"
+            })
+        ));
     }
 }
@@ -0,0 +1,328 @@
+use super::*;
+
+/// [`parse_kana`]の失敗。
+#[derive(Clone, PartialEq, Eq, Debug, thiserror::Error)]
+pub enum KanaParseError {
+    #[error("unexpected character {character:?} at byte {position}")]
+    UnexpectedChar { position: usize, character: char },
+    #[error("empty accent phrase at byte {position}")]
+    EmptyAccentPhrase { position: usize },
+    #[error("second accent mark in an accent phrase at byte {position}")]
+    MultipleAccents { position: usize },
+    #[error("accent mark at byte {position} does not follow a mora")]
+    MisplacedAccent { position: usize },
+    #[error("unvoiced mark at byte {position} is not followed by a mora")]
+    MisplacedUnvoiced { position: usize },
+    #[error("interrogative mark at byte {position} does not end an accent phrase")]
+    MisplacedInterrogative { position: usize },
+    #[error("accent phrase ending at byte {position} has no accent mark")]
+    MissingAccent { position: usize },
+}
+
+impl KanaParseError {
+    /// 問題のある箇所のバイトオフセット。
+    pub fn position(&self) -> usize {
+        match *self {
+            Self::UnexpectedChar { position, .. }
+            | Self::EmptyAccentPhrase { position }
+            | Self::MultipleAccents { position }
+            | Self::MisplacedAccent { position }
+            | Self::MisplacedUnvoiced { position }
+            | Self::MisplacedInterrogative { position }
+            | Self::MissingAccent { position } => position,
+        }
+    }
+}
+
+const ACCENT_MARK: char = '\'';
+const UNVOICED_MARK: char = '_';
+const INTERROGATIVE_MARK: char = '？';
+const PHRASE_SEPARATOR: char = '/';
+const PAUSE_SEPARATOR: char = '、';
+
+/// アクセント句をAquesTalk風の記法(`コンニチワ'、ミ_ナ'サン`)で表す。
+///
+/// アクセント核のモーラの後に`'`、無声化されたモーラの前に`_`、疑問形のアクセント句の後に`？`を置き、
+/// アクセント句を`/`(ポーズがあれば`、`)で区切る。
+///
+/// VOICEVOXの記法と同じく、すべてのアクセント句に`'`を付ける。平板型(`accent`が0)のアクセント句は
+/// 最後のモーラの後に`'`を置くので、[`parse_kana`]で読み戻すと`accent`はモーラ数になる。
+pub fn accent_phrases_to_kana(accent_phrases: &[AccentPhrase]) -> String {
+    let mut kana = String::new();
+    for (i, phrase) in accent_phrases.iter().enumerate() {
+        let accent = match phrase.accent {
+            0 => phrase.moras.len(),
+            accent => accent,
+        };
+        for (j, mora) in phrase.moras.iter().enumerate() {
+            if mora.unvoiced {
+                kana.push(UNVOICED_MARK);
+            }
+            kana.push_str(&mora.text);
+            if j + 1 == accent {
+                kana.push(ACCENT_MARK);
+            }
+        }
+        if phrase.is_interrogative {
+            kana.push(INTERROGATIVE_MARK);
+        }
+        if i + 1 < accent_phrases.len() {
+            kana.push(if phrase.pause {
+                PAUSE_SEPARATOR
+            } else {
+                PHRASE_SEPARATOR
+            });
+        }
+    }
+    kana
+}
+
+/// [`accent_phrases_to_kana`]の記法を解釈する。VOICEVOXと同じく、`'`のないアクセント句はエラーとする。
+///
+/// 平板型と最後のモーラにアクセント核のある型は区別できないので、`accent`が0になることはない。
+///
+/// 各モーラとアクセント句の[`SourceSpan`]は`kana`上の範囲になる。
+pub fn parse_kana(kana: &str) -> Result<Vec<AccentPhrase>, KanaParseError> {
+    let mut parser = KanaParser {
+        kana,
+        phrases: vec![],
+        moras: vec![],
+        accent: None,
+        interrogative: None,
+        unvoiced: None,
+    };
+    let mut chars = kana.char_indices().enumerate().peekable();
+    while let Some((char_index, (position, c))) = chars.next() {
+        match c {
+            UNVOICED_MARK => {
+                parser.check_no_unvoiced()?;
+                parser.check_no_interrogative()?;
+                parser.unvoiced = Some((position, char_index));
+            }
+            ACCENT_MARK => {
+                parser.check_no_unvoiced()?;
+                if parser.moras.is_empty() || parser.interrogative.is_some() {
+                    return Err(KanaParseError::MisplacedAccent { position });
+                }
+                if parser.accent.is_some() {
+                    return Err(KanaParseError::MultipleAccents { position });
+                }
+                parser.accent = Some(parser.moras.len());
+            }
+            INTERROGATIVE_MARK => {
+                parser.check_no_unvoiced()?;
+                if parser.moras.is_empty() || parser.interrogative.is_some() {
+                    return Err(KanaParseError::MisplacedInterrogative { position });
+                }
+                parser.interrogative = Some(position);
+            }
+            PHRASE_SEPARATOR | PAUSE_SEPARATOR => {
+                parser.finish_phrase(position, c == PAUSE_SEPARATOR)?;
+            }
+            c if is_mora_head(c) => {
+                parser.check_no_interrogative()?;
+                let mut end = (position + c.len_utf8(), char_index + 1);
+                while let Some(&(char_index, (position, c))) = chars.peek() {
+                    if !is_small_kana(c) {
+                        break;
+                    }
+                    end = (position + c.len_utf8(), char_index + 1);
+                    chars.next();
+                }
+                let start = parser
+                    .unvoiced
+                    .take()
+                    .map_or((position, char_index), |start| start);
+                parser.moras.push(Mora {
+                    text: kana[position..end.0].to_owned(),
+                    unvoiced: start.0 != position,
+                    span: SourceSpan {
+                        range: start.0..end.0,
+                        char_range: start.1..end.1,
+                    },
+                });
+            }
+            character => {
+                return Err(KanaParseError::UnexpectedChar {
+                    position,
+                    character,
+                })
+            }
+        }
+    }
+    parser.finish_phrase(kana.len(), false)?;
+    Ok(parser.phrases)
+}
+
+struct KanaParser<'a> {
+    kana: &'a str,
+    phrases: Vec<AccentPhrase>,
+    moras: Vec<Mora>,
+    accent: Option<usize>,
+    /// `？`のバイトオフセット。
+    interrogative: Option<usize>,
+    /// `_`のバイトオフセットと文字単位の位置。
+    unvoiced: Option<(usize, usize)>,
+}
+
+impl KanaParser<'_> {
+    fn check_no_unvoiced(&self) -> Result<(), KanaParseError> {
+        match self.unvoiced {
+            Some((position, _)) => Err(KanaParseError::MisplacedUnvoiced { position }),
+            None => Ok(()),
+        }
+    }
+
+    fn check_no_interrogative(&self) -> Result<(), KanaParseError> {
+        match self.interrogative {
+            Some(position) => Err(KanaParseError::MisplacedInterrogative { position }),
+            None => Ok(()),
+        }
+    }
+
+    fn finish_phrase(&mut self, position: usize, pause: bool) -> Result<(), KanaParseError> {
+        self.check_no_unvoiced()?;
+        let moras = std::mem::take(&mut self.moras);
+        let (Some(first), Some(last)) = (moras.first(), moras.last()) else {
+            return Err(KanaParseError::EmptyAccentPhrase { position });
+        };
+        let Some(accent) = self.accent.take() else {
+            return Err(KanaParseError::MissingAccent { position });
+        };
+        let start = (first.span.range.start, first.span.char_range.start);
+        let mut end = (last.span.range.end, last.span.char_range.end);
+        // `'`は最後のモーラの直後に、`？`はその後に置かれうる。
+        if self.kana[end.0..].starts_with(ACCENT_MARK) {
+            end = (end.0 + 1, end.1 + 1);
+        }
+        if let Some(interrogative) = self.interrogative {
+            end = (interrogative + INTERROGATIVE_MARK.len_utf8(), end.1 + 1);
+        }
+        self.phrases.push(AccentPhrase {
+            moras,
+            accent,
+            pause,
+            is_interrogative: self.interrogative.take().is_some(),
+            span: SourceSpan {
+                range: start.0..end.0,
+                char_range: start.1..end.1,
+            },
+        });
+        Ok(())
+    }
+}
+
+/// 単独でモーラになるカタカナかどうか。
+fn is_mora_head(c: char) -> bool {
+    matches!(c, 'ァ'..='ヴ' | 'ー') && !is_small_kana(c)
+}
+
+/// 直前の文字と合わせて1モーラになる小書き文字かどうか。
+fn is_small_kana(c: char) -> bool {
+    matches!(
+        c,
+        'ァ' | 'ィ' | 'ゥ' | 'ェ' | 'ォ' | 'ャ' | 'ュ' | 'ョ' | 'ヮ'
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    /// (モーラと無声化, アクセント, ポーズ, 疑問形)
+    type Summary<'a> = (Vec<(&'a str, bool)>, usize, bool, bool);
+
+    fn summary(phrases: &[AccentPhrase]) -> Vec<Summary<'_>> {
+        phrases
+            .iter()
+            .map(|phrase| {
+                (
+                    phrase
+                        .moras
+                        .iter()
+                        .map(|mora| (&*mora.text, mora.unvoiced))
+                        .collect(),
+                    phrase.accent,
+                    phrase.pause,
+                    phrase.is_interrogative,
+                )
+            })
+            .collect()
+    }
+
+    #[rstest]
+    fn parse_kana_works() {
+        let phrases = parse_kana("コンニチワ'、ミ_ナ'サン/キョーワ'？").unwrap();
+        assert_eq!(
+            vec![
+                (
+                    vec![
+                        ("コ", false),
+                        ("ン", false),
+                        ("ニ", false),
+                        ("チ", false),
+                        ("ワ", false)
+                    ],
+                    5,
+                    true,
+                    false
+                ),
+                (
+                    vec![("ミ", false), ("ナ", true), ("サ", false), ("ン", false)],
+                    2,
+                    false,
+                    false
+                ),
+                (
+                    vec![("キョ", false), ("ー", false), ("ワ", false)],
+                    3,
+                    false,
+                    true
+                ),
+            ],
+            summary(&phrases)
+        );
+        assert_eq!(0..16, phrases[0].span.range);
+        assert_eq!(7..13, phrases[1].span.char_range);
+        assert_eq!(8..10, phrases[1].moras[1].span.char_range);
+        assert_eq!(14..20, phrases[2].span.char_range);
+    }
+
+    #[rstest]
+    #[case("コンニチワ'、ミ_ナ'サン/キョ'ーワ？")]
+    #[case("ア'/_シ'タ")]
+    #[case("ハシ'")]
+    fn accent_phrases_to_kana_round_trips(#[case] kana: &str) {
+        assert_eq!(kana, accent_phrases_to_kana(&parse_kana(kana).unwrap()));
+    }
+
+    #[rstest]
+    fn accent_phrases_to_kana_marks_flat_phrases_at_the_last_mora() {
+        let mut phrases = parse_kana("ハシ'/ア'メ").unwrap();
+        phrases[0].accent = 0;
+        assert_eq!("ハシ'/ア'メ", accent_phrases_to_kana(&phrases));
+        // 読み戻すと、平板型はアクセント核が最後のモーラにあるものとして扱われる
+        assert_eq!(2, parse_kana("ハシ'").unwrap()[0].accent);
+    }
+
+    #[rstest]
+    #[case("", KanaParseError::EmptyAccentPhrase { position: 0 })]
+    #[case("ア'/", KanaParseError::EmptyAccentPhrase { position: 5 })]
+    #[case("ア'//イ'", KanaParseError::EmptyAccentPhrase { position: 5 })]
+    #[case("ハシ", KanaParseError::MissingAccent { position: 6 })]
+    #[case("ア'/イ", KanaParseError::MissingAccent { position: 8 })]
+    #[case("アa", KanaParseError::UnexpectedChar { position: 3, character: 'a' })]
+    #[case("ャア", KanaParseError::UnexpectedChar { position: 0, character: 'ャ' })]
+    #[case("ア'イ'", KanaParseError::MultipleAccents { position: 7 })]
+    #[case("'ア", KanaParseError::MisplacedAccent { position: 0 })]
+    #[case("ア_", KanaParseError::MisplacedUnvoiced { position: 3 })]
+    #[case("ア_'", KanaParseError::MisplacedUnvoiced { position: 3 })]
+    #[case("ア？イ", KanaParseError::MisplacedInterrogative { position: 3 })]
+    #[case("？", KanaParseError::MisplacedInterrogative { position: 0 })]
+    fn parse_kana_fails(#[case] kana: &str, #[case] expected: KanaParseError) {
+        let error = parse_kana(kana).unwrap_err();
+        assert_eq!(expected.position(), error.position());
+        assert_eq!(expected, error);
+    }
+}
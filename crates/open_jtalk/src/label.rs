@@ -0,0 +1,207 @@
+use super::*;
+use std::{fmt, str::FromStr};
+
+/// HTS形式のフルコンテキストラベルを分解したもの。
+///
+/// 各フィールドはHTSの表記(`p1`〜`p5`, `A1`〜`K3`)の順に並べた配列で、`xx`は`None`になる。
+/// 例えば`a[0]`は`A1`(アクセント核からの相対位置)、`f[0]`は`F1`(次のアクセント句のモーラ数)である。
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FullContextLabel {
+    /// 前々・前・現在・次・次々の音素(`p1`〜`p5`)。
+    pub phonemes: [Option<String>; 5],
+    pub a: [Option<i32>; 3],
+    /// 品詞・活用型・活用形のコード(`B1`〜`D3`)。`09`のように0埋めされるので文字列のまま持つ。
+    pub b: [Option<String>; 3],
+    pub c: [Option<String>; 3],
+    pub d: [Option<String>; 3],
+    pub e: [Option<i32>; 5],
+    pub f: [Option<i32>; 8],
+    pub g: [Option<i32>; 5],
+    pub h: [Option<i32>; 2],
+    pub i: [Option<i32>; 8],
+    pub j: [Option<i32>; 2],
+    pub k: [Option<i32>; 3],
+}
+
+/// 各フィールドの直前に置かれる区切り。
+const SEPARATORS: [&str; 50] = [
+    "", "^", "-", "+", "=", // p
+    "/A:", "+", "+", // A
+    "/B:", "-", "_", // B
+    "/C:", "_", "+", // C
+    "/D:", "+", "_", // D
+    "/E:", "_", "!", "_", "-", // E
+    "/F:", "_", "#", "_", "@", "_", "|", "_", // F
+    "/G:", "_", "%", "_", "_", // G
+    "/H:", "_", // H
+    "/I:", "-", "@", "+", "&", "-", "|", "+", // I
+    "/J:", "_", // J
+    "/K:", "+", "-", // K
+];
+
+const UNDEFINED: &str = "xx";
+
+impl FullContextLabel {
+    /// 現在の音素(`p3`)。
+    pub fn phoneme(&self) -> Option<&str> {
+        self.phonemes[2].as_deref()
+    }
+
+    fn values(&self) -> Vec<String> {
+        let text = |s: &Option<String>| s.as_deref().unwrap_or(UNDEFINED).to_owned();
+        let number = |n: &Option<i32>| n.map_or_else(|| UNDEFINED.to_owned(), |n| n.to_string());
+        let mut values = vec![];
+        values.extend(self.phonemes.iter().map(text));
+        values.extend(self.a.iter().map(number));
+        for codes in [&self.b, &self.c, &self.d] {
+            values.extend(codes.iter().map(text));
+        }
+        for numbers in [
+            &self.e[..],
+            &self.f,
+            &self.g,
+            &self.h,
+            &self.i,
+            &self.j,
+            &self.k,
+        ] {
+            values.extend(numbers.iter().map(number));
+        }
+        values
+    }
+}
+
+impl FromStr for FullContextLabel {
+    type Err = Error;
+
+    fn from_str(label: &str) -> Result<Self> {
+        let error = || Error::Parse {
+            what: "full-context label",
+            input: label.to_owned(),
+        };
+
+        let mut values = Vec::with_capacity(SEPARATORS.len());
+        let mut rest = label;
+        for (i, separator) in SEPARATORS.iter().enumerate() {
+            rest = rest.strip_prefix(separator).ok_or_else(error)?;
+            let end = match SEPARATORS.get(i + 1) {
+                Some(next) => rest.find(next).ok_or_else(error)?,
+                None => rest.len(),
+            };
+            values.push(&rest[..end]);
+            rest = &rest[end..];
+        }
+
+        let text = |i: usize| (values[i] != UNDEFINED).then(|| values[i].to_owned());
+        let texts = |start: usize| [text(start), text(start + 1), text(start + 2)];
+        Ok(Self {
+            phonemes: [text(0), text(1), text(2), text(3), text(4)],
+            a: parse_numbers(&values[5..8]).ok_or_else(error)?,
+            b: texts(8),
+            c: texts(11),
+            d: texts(14),
+            e: parse_numbers(&values[17..22]).ok_or_else(error)?,
+            f: parse_numbers(&values[22..30]).ok_or_else(error)?,
+            g: parse_numbers(&values[30..35]).ok_or_else(error)?,
+            h: parse_numbers(&values[35..37]).ok_or_else(error)?,
+            i: parse_numbers(&values[37..45]).ok_or_else(error)?,
+            j: parse_numbers(&values[45..47]).ok_or_else(error)?,
+            k: parse_numbers(&values[47..50]).ok_or_else(error)?,
+        })
+    }
+}
+
+fn parse_numbers<const N: usize>(values: &[&str]) -> Option<[Option<i32>; N]> {
+    values
+        .iter()
+        .map(|&s| {
+            if s == UNDEFINED {
+                return Some(None);
+            }
+            s.parse().ok().map(Some)
+        })
+        .collect::<Option<Vec<_>>>()?
+        .try_into()
+        .ok()
+}
+
+impl fmt::Display for FullContextLabel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (separator, value) in SEPARATORS.iter().zip(self.values()) {
+            write!(f, "{separator}{value}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const LABEL: &str = "xx^xx-sil+k=o/A:-3+1+4/B:xx-xx_xx/C:xx_xx+xx/D:09+xx_xx/E:xx_xx!xx_xx-xx/F:4_1#0_xx@1_1|1_4/G:xx_xx%xx_xx_xx/H:xx_xx/I:xx-xx@xx+xx&xx-xx|xx+xx/J:1_4/K:1+1-4";
+
+    #[rstest]
+    fn full_context_label_parse_works() {
+        let label = LABEL.parse::<FullContextLabel>().unwrap();
+        assert_eq!(Some("sil"), label.phoneme());
+        assert_eq!(
+            [
+                None,
+                None,
+                Some("sil".to_owned()),
+                Some("k".to_owned()),
+                Some("o".to_owned())
+            ],
+            label.phonemes
+        );
+        assert_eq!([Some(-3), Some(1), Some(4)], label.a);
+        assert_eq!([Some("09".to_owned()), None, None], label.d);
+        assert_eq!(
+            [
+                Some(4),
+                Some(1),
+                Some(0),
+                None,
+                Some(1),
+                Some(1),
+                Some(1),
+                Some(4)
+            ],
+            label.f
+        );
+        assert_eq!([Some(1), Some(1), Some(4)], label.k);
+    }
+
+    #[rstest]
+    fn full_context_label_display_round_trips() {
+        let label = LABEL.parse::<FullContextLabel>().unwrap();
+        assert_eq!(LABEL, label.to_string());
+        assert_eq!(label, label.to_string().parse().unwrap());
+    }
+
+    #[cfg(feature = "serde")]
+    #[rstest]
+    fn full_context_label_serde_works() {
+        let label = LABEL.parse::<FullContextLabel>().unwrap();
+        let value = serde_json::to_value(&label).unwrap();
+        assert_eq!(
+            serde_json::json!([null, null, "sil", "k", "o"]),
+            value["phonemes"]
+        );
+        assert_eq!(serde_json::json!([-3, 1, 4]), value["a"]);
+        assert_eq!(label, serde_json::from_value(value).unwrap());
+    }
+
+    #[rstest]
+    #[case("")]
+    #[case("xx^xx-sil+k=o")]
+    #[case(&LABEL.replace("/A:-3", "/A:y"))]
+    fn full_context_label_parse_fails(#[case] input: &str) {
+        assert!(matches!(
+            input.parse::<FullContextLabel>(),
+            Err(Error::Parse { .. })
+        ));
+    }
+}
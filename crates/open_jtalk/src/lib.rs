@@ -1,18 +1,74 @@
+mod accent_phrase;
+mod alignment;
+#[cfg(feature = "tokio")]
+mod async_open_jtalk;
+mod cancel;
+mod digit;
+mod english;
+mod error;
 mod jpcommon;
+mod kana;
+mod label;
 mod mecab;
+mod morpheme;
 mod njd;
+mod njd_pass;
+mod open_jtalk;
+mod pipeline;
+mod pool;
+mod prosody;
+mod reading_override;
 mod resource;
+mod sentence;
 mod text2mecab;
+mod user_dict;
 
+pub use accent_phrase::*;
+pub use alignment::*;
+#[cfg(feature = "tokio")]
+pub use async_open_jtalk::*;
+pub use cancel::*;
+pub use digit::*;
+pub use english::*;
+pub use error::*;
 pub use jpcommon::*;
+pub use kana::*;
+pub use label::*;
 pub use mecab::*;
+pub use morpheme::*;
 pub use njd::*;
+pub use njd_pass::*;
+pub use open_jtalk::*;
+pub use pipeline::*;
+pub use pool::*;
+pub use prosody::*;
+pub use reading_override::*;
 pub use resource::*;
+pub use sentence::*;
 pub use text2mecab::*;
+pub use user_dict::*;
 
 #[cfg(test)]
 use rstest::rstest;
 
+/// C側の`char **`と要素数から、要素のポインタのスライスを作る。
+///
+/// # Safety
+///
+/// `ptr`がnullでなければ、`size`個の要素を持ち、`'a`の間有効でなければならない。
+unsafe fn c_str_array<'a>(
+    ptr: *mut *mut std::os::raw::c_char,
+    size: i32,
+) -> Option<&'a [*mut std::os::raw::c_char]> {
+    if ptr.is_null() {
+        return None;
+    }
+    Some(std::slice::from_raw_parts(
+        ptr,
+        usize::try_from(size).unwrap_or(0),
+    ))
+}
+
 #[inline]
 fn bool_number_to_bool(bool_number: i32) -> bool {
     bool_number == 1
@@ -1,10 +1,16 @@
 mod mecab_dict_index;
+mod model;
 
 pub use mecab_dict_index::*;
+pub use model::*;
 
 use super::*;
 use camino::{Utf8Path, Utf8PathBuf};
-use std::{ffi::CString, mem::MaybeUninit};
+use std::{
+    ffi::{CStr, CString},
+    fmt,
+    os::raw::c_char,
+};
 
 #[derive(thiserror::Error, Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Hash)]
 pub enum MecabLoadError {
@@ -14,51 +20,145 @@ pub enum MecabLoadError {
     Nul { filename: Utf8PathBuf },
 }
 
-#[derive(Default)]
-pub struct Mecab(Option<open_jtalk_sys::Mecab>);
+/// MeCabによる形態素解析器。
+///
+/// C側の構造体はヒープ上に確保するので、`Vec`に入れたりスレッド間で受け渡したりしても構わない。
+pub struct Mecab {
+    raw: RawBox<open_jtalk_sys::Mecab>,
+    /// [`Mecab::with_model`]で作った場合に共有している辞書。
+    shared_model: Option<MecabModel>,
+}
 
-pub struct MecabFeature;
+/// [`Mecab::analysis`]で得られた素性の列。
+///
+/// `Mecab`を借用しているので、保持している間は`Mecab::refresh`などを呼べない。
+///
+/// ```compile_fail
+/// # let mut mecab = open_jtalk::Mecab::new().unwrap();
+/// let features = mecab.get_feature().unwrap();
+/// mecab.refresh().unwrap();
+/// drop(features);
+/// ```
+#[derive(Clone, Copy)]
+pub struct MecabFeatures<'a>(&'a [*mut c_char]);
 
 unsafe impl resources::Resource for Mecab {
     unsafe fn initialize(&mut self) -> bool {
-        if self.0.is_some() {
-            panic!("already initialized mecab");
+        if self.raw.is_initialized() {
+            return false;
         }
-        let (result, m) = {
-            let mut m = MaybeUninit::<open_jtalk_sys::Mecab>::uninit();
-            let result = bool_number_to_bool(open_jtalk_sys::Mecab_initialize(m.as_mut_ptr()));
-            (result, m.assume_init())
-        };
-        self.0 = Some(m);
+        let result =
+            bool_number_to_bool(open_jtalk_sys::Mecab_initialize(self.raw.as_uninit_ptr()));
+        self.raw.set_initialized(true);
         result
     }
     unsafe fn clear(&mut self) -> bool {
-        let result = bool_number_to_bool(open_jtalk_sys::Mecab_clear(self.as_raw_ptr()));
-        self.0 = None;
+        let Ok(m) = self.as_raw_ptr() else {
+            return false;
+        };
+        let model = self.detach_model(m);
+        let result = bool_number_to_bool(open_jtalk_sys::Mecab_clear(m));
+        // 辞書から作ったtaggerとlatticeが破棄されてから辞書を手放す
+        drop(model);
+        self.raw.set_initialized(false);
         result
     }
 }
 
+impl Drop for Mecab {
+    fn drop(&mut self) {
+        unsafe {
+            resources::Resource::clear(self);
+        }
+    }
+}
+
 // SAFETY: `Send`と対立する性質はないはず。
 unsafe impl Send for Mecab {}
 
+impl fmt::Debug for Mecab {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let features = self.get_feature().ok().flatten().map(|features| {
+            features
+                .iter()
+                .map(CStr::to_string_lossy)
+                .collect::<Vec<_>>()
+        });
+        f.debug_struct("Mecab")
+            .field("features", &features)
+            .field("shared_model", &self.shared_model.is_some())
+            .finish()
+    }
+}
+
 impl Mecab {
-    unsafe fn as_raw_ptr(&self) -> *mut open_jtalk_sys::Mecab {
-        if self.0.is_none() {
-            panic!("uninitialized mecab");
+    pub fn new() -> Result<Self> {
+        let mut mecab = Self {
+            raw: RawBox::new(),
+            shared_model: None,
+        };
+        if !unsafe { resources::Resource::initialize(&mut mecab) } {
+            return Err(Error::Initialize { resource: "mecab" });
+        }
+        Ok(mecab)
+    }
+
+    unsafe fn as_raw_ptr(&self) -> Result<*mut open_jtalk_sys::Mecab> {
+        self.raw
+            .get()
+            .ok_or(Error::Uninitialized { resource: "mecab" })
+    }
+
+    /// `model`を共有する`Mecab`を作る。辞書を読み込み直す必要がないので、[`Mecab::load`]より軽い。
+    pub fn with_model(model: &MecabModel) -> Result<Self> {
+        let mut mecab = Self::new()?;
+        unsafe {
+            let m = mecab.as_raw_ptr()?;
+            let raw_model = model.as_raw_ptr();
+            (*m).model = raw_model.cast();
+            mecab.shared_model = Some(model.clone());
+            (*m).tagger = open_jtalk_sys::mecab_model_new_tagger(raw_model).cast();
+            if (*m).tagger.is_null() {
+                return Err(Error::Unsuccessful {
+                    function: "mecab_model_new_tagger",
+                });
+            }
+            (*m).lattice = open_jtalk_sys::mecab_model_new_lattice(raw_model).cast();
+            if (*m).lattice.is_null() {
+                return Err(Error::Unsuccessful {
+                    function: "mecab_model_new_lattice",
+                });
+            }
         }
-        self.0.as_ref().unwrap() as *const open_jtalk_sys::Mecab as *mut open_jtalk_sys::Mecab
+        Ok(mecab)
     }
 
-    pub fn load(&mut self, dic_dir: impl AsRef<Utf8Path>) -> Result<(), MecabLoadError> {
+    /// 共有している辞書を`Mecab_clear`で解放しないように切り離す。
+    ///
+    /// 切り離した辞書は、`Mecab_clear`などでtaggerとlatticeが破棄されるまで手放してはならない。
+    #[must_use]
+    unsafe fn detach_model(&mut self, m: *mut open_jtalk_sys::Mecab) -> Option<MecabModel> {
+        let model = self.shared_model.take();
+        if model.is_some() {
+            (*m).model = std::ptr::null_mut();
+        }
+        model
+    }
+
+    pub fn load(&mut self, dic_dir: impl AsRef<Utf8Path>) -> Result<()> {
         let dic_dir = c_filename(dic_dir.as_ref())?;
         let success = bool_number_to_bool(unsafe {
-            open_jtalk_sys::Mecab_load(self.as_raw_ptr(), dic_dir.as_ptr())
+            let m = self.as_raw_ptr()?;
+            let model = self.detach_model(m);
+            let result = open_jtalk_sys::Mecab_load(m, dic_dir.as_ptr());
+            drop(model);
+            result
         });
         if !success {
             return Err(MecabLoadError::Unsuccessful {
                 function: "Mecab_load",
-            });
+            }
+            .into());
         }
         Ok(())
     }
@@ -67,68 +167,115 @@ impl Mecab {
         &mut self,
         dic_dir: &Utf8Path,
         userdic: Option<&Utf8Path>,
-    ) -> Result<(), MecabLoadError> {
+    ) -> Result<()> {
         let dic_dir = c_filename(dic_dir)?;
         let userdic = &userdic.map(c_filename).transpose()?;
         let success = bool_number_to_bool(unsafe {
-            open_jtalk_sys::Mecab_load_with_userdic(
-                self.as_raw_ptr(),
+            let m = self.as_raw_ptr()?;
+            let model = self.detach_model(m);
+            let result = open_jtalk_sys::Mecab_load_with_userdic(
+                m,
                 dic_dir.as_ptr(),
                 match userdic {
                     Some(userdic) => userdic.as_ptr(),
                     None => std::ptr::null(),
                 },
-            )
+            );
+            drop(model);
+            result
         });
         if !success {
             return Err(MecabLoadError::Unsuccessful {
                 function: "Mecab_load_with_userdic",
-            });
+            }
+            .into());
         }
         Ok(())
     }
-    pub fn get_feature(&self) -> Option<&MecabFeature> {
+    pub fn get_feature(&self) -> Result<Option<MecabFeatures<'_>>> {
         unsafe {
-            let feature = open_jtalk_sys::Mecab_get_feature(self.as_raw_ptr());
-            if !feature.is_null() {
-                Some(&*(feature as *const MecabFeature))
-            } else {
-                None
-            }
+            let mecab = self.as_raw_ptr()?;
+            Ok(c_str_array(
+                open_jtalk_sys::Mecab_get_feature(mecab),
+                open_jtalk_sys::Mecab_get_size(mecab),
+            )
+            .map(MecabFeatures))
         }
     }
 
-    pub fn get_feature_mut(&mut self) -> Option<&mut MecabFeature> {
-        unsafe {
-            let feature = open_jtalk_sys::Mecab_get_feature(self.as_raw_ptr());
-            if !feature.is_null() {
-                Some(&mut *(feature as *mut MecabFeature))
-            } else {
-                None
+    pub fn analysis(&mut self, str: impl AsRef<str>) -> Result<()> {
+        let str = CString::new(str.as_ref())?;
+        let success = unsafe {
+            bool_number_to_bool(open_jtalk_sys::Mecab_analysis(
+                self.as_raw_ptr()?,
+                str.as_ptr(),
+            ))
+        };
+        if !success {
+            return Err(Error::Unsuccessful {
+                function: "Mecab_analysis",
+            });
+        }
+        Ok(())
+    }
+
+    /// [`Mecab::print`]が標準出力に書き出す内容(素性を1行ずつ並べたもの)を返す。
+    pub fn to_debug_string(&self) -> Result<String> {
+        let mut output = String::new();
+        if let Some(features) = self.get_feature()? {
+            for feature in features.iter() {
+                output += &feature.to_string_lossy();
+                output.push('\n');
             }
         }
+        Ok(output)
     }
 
-    pub fn analysis(&mut self, str: impl AsRef<str>) -> bool {
-        let str = CString::new(str.as_ref()).unwrap();
-        unsafe {
-            bool_number_to_bool(open_jtalk_sys::Mecab_analysis(
-                self.as_raw_ptr(),
-                str.as_ptr(),
-            ))
+    pub fn print(&mut self) -> Result<()> {
+        let success =
+            unsafe { bool_number_to_bool(open_jtalk_sys::Mecab_print(self.as_raw_ptr()?)) };
+        if !success {
+            return Err(Error::Unsuccessful {
+                function: "Mecab_print",
+            });
+        }
+        Ok(())
+    }
+
+    pub fn get_size(&self) -> Result<i32> {
+        unsafe { Ok(open_jtalk_sys::Mecab_get_size(self.as_raw_ptr()?)) }
+    }
+
+    pub fn refresh(&mut self) -> Result<()> {
+        let success =
+            unsafe { bool_number_to_bool(open_jtalk_sys::Mecab_refresh(self.as_raw_ptr()?)) };
+        if !success {
+            return Err(Error::Unsuccessful {
+                function: "Mecab_refresh",
+            });
         }
+        Ok(())
+    }
+}
+
+impl<'a> MecabFeatures<'a> {
+    pub fn len(&self) -> usize {
+        self.0.len()
     }
 
-    pub fn print(&mut self) -> bool {
-        unsafe { bool_number_to_bool(open_jtalk_sys::Mecab_print(self.as_raw_ptr())) }
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
     }
 
-    pub fn get_size(&self) -> i32 {
-        unsafe { open_jtalk_sys::Mecab_get_size(self.as_raw_ptr()) }
+    /// 各素性を`&CStr`として返す。
+    pub fn iter(&self) -> impl Iterator<Item = &'a CStr> + 'a {
+        self.0
+            .iter()
+            .map(|&feature| unsafe { CStr::from_ptr(feature) })
     }
 
-    pub fn refresh(&mut self) -> bool {
-        unsafe { bool_number_to_bool(open_jtalk_sys::Mecab_refresh(self.as_raw_ptr())) }
+    pub(crate) fn as_ptr(&self) -> *mut *mut c_char {
+        self.0.as_ptr() as *mut *mut c_char
     }
 }
 
@@ -147,22 +294,45 @@ mod tests {
 
     #[rstest]
     fn mecab_initialize_and_clear_works() {
-        let mut mecab = Mecab::default();
+        let mut mecab = Mecab::new().unwrap();
         unsafe {
-            assert!(mecab.initialize());
+            assert!(!mecab.initialize());
             assert!(mecab.clear());
+            assert!(!mecab.clear());
+            assert!(mecab.initialize());
+        }
+    }
+
+    #[rstest]
+    fn mecab_cleared_returns_error() {
+        let mut mecab = Mecab::new().unwrap();
+        unsafe {
+            mecab.clear();
         }
+        assert!(matches!(
+            mecab.get_size(),
+            Err(Error::Uninitialized { resource: "mecab" })
+        ));
+    }
+
+    #[rstest]
+    fn mecab_analysis_with_nul_returns_error() {
+        let mut mecab = Mecab::new().unwrap();
+        assert!(matches!(
+            mecab.analysis("あ\0い"),
+            Err(Error::Nul { position: 3 })
+        ));
     }
 
     #[rstest]
-    fn mecab_get_feature_mut_works() {
-        let mut mecab = ManagedResource::<Mecab>::initialize();
-        assert!(mecab.get_feature_mut().is_none());
+    fn mecab_get_feature_before_analysis_works() {
+        let mecab = Mecab::new().unwrap();
+        assert!(mecab.get_feature().unwrap().is_none());
     }
 
     #[rstest]
     fn mecab_load_works() {
-        let mut mecab = ManagedResource::<Mecab>::initialize();
+        let mut mecab = Mecab::new().unwrap();
         mecab
             .load(
                 Utf8Path::new(std::env!("CARGO_MANIFEST_DIR"))
@@ -173,14 +343,14 @@ mod tests {
 
     #[rstest]
     fn mecab_get_size_before_analysis_works() {
-        let mecab = ManagedResource::<Mecab>::initialize();
-        assert_eq!(0, mecab.get_size());
+        let mecab = Mecab::new().unwrap();
+        assert_eq!(0, mecab.get_size().unwrap());
     }
 
     #[rstest]
     #[case("h^o-d+e=s/A:2+3+2/B:22-xx_xx/C:10_7+2/D:xx+xx_xx/E:5_5!0_xx-0/F:4_1#0_xx@1_1|1_4/G:xx_xx%xx_xx_xx/H:1_5/I:1-4@2+1&2-1|6+4/J:xx_xx/K:2+2-9",true)]
     fn mecab_analysis_works(#[case] input: &str, #[case] expected: bool) {
-        let mut mecab = ManagedResource::<Mecab>::initialize();
+        let mut mecab = Mecab::new().unwrap();
         mecab
             .load(
                 Utf8Path::new(std::env!("CARGO_MANIFEST_DIR"))
@@ -188,20 +358,40 @@ mod tests {
             )
             .unwrap();
         let s = text2mecab(input).unwrap();
-        assert_eq!(expected, mecab.analysis(s));
-        assert_ne!(0, mecab.get_size());
-        assert!(mecab.get_feature_mut().is_some());
+        assert_eq!(expected, mecab.analysis(s).is_ok());
+        assert_ne!(0, mecab.get_size().unwrap());
+        let features = mecab.get_feature().unwrap().unwrap();
+        assert_eq!(mecab.get_size().unwrap() as usize, features.len());
+        assert!(features
+            .iter()
+            .all(|feature| !feature.to_bytes().is_empty()));
+    }
+
+    #[rstest]
+    fn mecab_to_debug_string_works() {
+        let mut mecab = Mecab::new().unwrap();
+        assert_eq!("", mecab.to_debug_string().unwrap());
+        mecab
+            .load(
+                Utf8Path::new(std::env!("CARGO_MANIFEST_DIR"))
+                    .join("src/mecab/testdata/mecab_load"),
+            )
+            .unwrap();
+        mecab.analysis(text2mecab("こんにちは").unwrap()).unwrap();
+        let dump = mecab.to_debug_string().unwrap();
+        assert_eq!(mecab.get_size().unwrap() as usize, dump.lines().count());
+        assert!(format!("{mecab:?}").contains(dump.lines().next().unwrap()));
     }
 
     #[rstest]
     fn mecab_refresh_works() {
-        let mut mecab = ManagedResource::<Mecab>::initialize();
-        assert!(mecab.refresh());
+        let mut mecab = Mecab::new().unwrap();
+        mecab.refresh().unwrap();
     }
 
     #[rstest]
     fn mecab_print_works() {
-        let mut mecab = ManagedResource::<Mecab>::initialize();
-        assert!(mecab.print());
+        let mut mecab = Mecab::new().unwrap();
+        mecab.print().unwrap();
     }
 }
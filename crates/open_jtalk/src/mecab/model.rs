@@ -0,0 +1,206 @@
+use super::*;
+use open_jtalk_sys as sys;
+use std::{ffi::CStr, sync::Arc};
+
+/// 読み込み済みの辞書。複製しても辞書は共有され、[`Mecab::with_model`]で複数の`Mecab`から使える。
+///
+/// MeCabのモデルは複数のスレッドから同時にタガーやラティスを作れるので、`Send`かつ`Sync`である。
+#[derive(Clone)]
+pub struct MecabModel(Arc<LoadedMecab>);
+
+/// 読み込んだ辞書ファイルの情報。
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct DictionaryInfo {
+    pub filename: String,
+    pub charset: String,
+    /// 登録されている単語数。
+    pub size: u32,
+    pub kind: DictionaryKind,
+    pub left_size: u32,
+    pub right_size: u32,
+    pub version: u16,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DictionaryKind {
+    System,
+    User,
+    Unknown,
+}
+
+/// 辞書を読み込んだ`Mecab`。モデル以外(タガーやラティス)には触れない。
+struct LoadedMecab(Mecab);
+
+// SAFETY: `MeCab::Model`はスレッドセーフであり、共有するのはモデルへのポインタのみ。
+unsafe impl Sync for LoadedMecab {}
+
+impl MecabModel {
+    pub fn load(dic_dir: impl AsRef<Utf8Path>) -> Result<Self> {
+        Self::load_with_userdic(dic_dir.as_ref(), None)
+    }
+
+    pub fn load_with_userdic(dic_dir: &Utf8Path, userdic: Option<&Utf8Path>) -> Result<Self> {
+        let mut mecab = Mecab::new()?;
+        mecab.load_with_userdic(dic_dir, userdic)?;
+        Ok(Self(Arc::new(LoadedMecab(mecab))))
+    }
+
+    /// システム辞書とユーザー辞書の情報を読み込んだ順に返す。
+    pub fn dictionary_info(&self) -> Result<Vec<DictionaryInfo>> {
+        let mut infos = vec![];
+        // SAFETY: 辞書の情報はモデルが生きている間有効。
+        unsafe {
+            let mut info = sys::mecab_model_dictionary_info(self.as_raw_ptr());
+            while let Some(i) = info.as_ref() {
+                infos.push(DictionaryInfo {
+                    filename: CStr::from_ptr(i.filename).to_str()?.to_owned(),
+                    charset: CStr::from_ptr(i.charset).to_str()?.to_owned(),
+                    size: i.size,
+                    kind: match i.type_ {
+                        t if t == sys::MECAB_SYS_DIC as i32 => DictionaryKind::System,
+                        t if t == sys::MECAB_USR_DIC as i32 => DictionaryKind::User,
+                        _ => DictionaryKind::Unknown,
+                    },
+                    left_size: i.lsize,
+                    right_size: i.rsize,
+                    version: i.version,
+                });
+                info = i.next;
+            }
+        }
+        Ok(infos)
+    }
+
+    /// 表層形が`word`と完全に一致する辞書の項目を返す。未知語は含まない。
+    ///
+    /// `word`はtext2mecabで変換した後の表記(全角)で与える。
+    pub fn lookup(&self, word: &str) -> Result<Vec<DictionaryEntry>> {
+        let model = self.as_raw_ptr();
+        // SAFETY: ラティスはこの関数の中でのみ使い、最後に破棄する。
+        unsafe {
+            let lattice = sys::mecab_model_new_lattice(model);
+            if lattice.is_null() {
+                return Err(Error::Unsuccessful {
+                    function: "mecab_model_new_lattice",
+                });
+            }
+            let range = word.as_bytes().as_ptr_range();
+            let mut node =
+                sys::mecab_model_lookup(model, range.start.cast(), range.end.cast(), lattice);
+            let mut entries = vec![];
+            let result = loop {
+                let Some(n) = node.as_ref() else {
+                    break Ok(entries);
+                };
+                if n.stat == sys::MECAB_NOR_NODE as u8 && usize::from(n.length) == word.len() {
+                    let feature = match CStr::from_ptr(n.feature).to_str() {
+                        Ok(feature) => feature,
+                        Err(e) => break Err(e.into()),
+                    };
+                    entries.push(DictionaryEntry {
+                        left_id: n.lcAttr,
+                        right_id: n.rcAttr,
+                        cost: n.wcost,
+                        morpheme: Morpheme::parse(&format!("{word},{feature}")),
+                    });
+                }
+                node = n.bnext;
+            };
+            sys::mecab_lattice_destroy(lattice);
+            result
+        }
+    }
+
+    pub(crate) fn as_raw_ptr(&self) -> *mut open_jtalk_sys::mecab_model_t {
+        // SAFETY: 読み込みに成功した`Mecab`は`clear`されない。
+        unsafe {
+            self.0
+                 .0
+                .as_raw_ptr()
+                .map_or(std::ptr::null_mut(), |m| (*m).model.cast())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn model() -> MecabModel {
+        MecabModel::load(
+            Utf8Path::new(std::env!("CARGO_MANIFEST_DIR")).join("src/mecab/testdata/mecab_load"),
+        )
+        .unwrap()
+    }
+
+    fn features(mecab: &mut Mecab, text: &str) -> Vec<String> {
+        mecab.analysis(text2mecab(text).unwrap()).unwrap();
+        let features = mecab
+            .get_feature()
+            .unwrap()
+            .unwrap()
+            .iter()
+            .map(|feature| feature.to_string_lossy().into_owned())
+            .collect();
+        mecab.refresh().unwrap();
+        features
+    }
+
+    #[rstest]
+    fn mecab_with_model_works() {
+        let model = model();
+        let mut loaded = Mecab::new().unwrap();
+        loaded
+            .load(
+                Utf8Path::new(std::env!("CARGO_MANIFEST_DIR"))
+                    .join("src/mecab/testdata/mecab_load"),
+            )
+            .unwrap();
+        let expected = features(&mut loaded, "こんにちは");
+
+        let mecabs = (0..2)
+            .map(|_| Mecab::with_model(&model).unwrap())
+            .collect::<Vec<_>>();
+        drop(model);
+        let handles = mecabs
+            .into_iter()
+            .map(|mut mecab| std::thread::spawn(move || features(&mut mecab, "こんにちは")))
+            .collect::<Vec<_>>();
+        for handle in handles {
+            assert_eq!(expected, handle.join().unwrap());
+        }
+    }
+
+    #[rstest]
+    fn mecab_model_dictionary_info_works() {
+        let infos = model().dictionary_info().unwrap();
+        assert!(infos.iter().any(|info| info.kind == DictionaryKind::System));
+    }
+
+    #[rstest]
+    fn mecab_model_lookup_ignores_unknown_words() {
+        assert_eq!(
+            Vec::<DictionaryEntry>::new(),
+            model().lookup("ｘｙｚ").unwrap()
+        );
+    }
+
+    #[rstest]
+    fn mecab_with_model_can_load_another_dictionary() {
+        let model = model();
+        let mut mecab = Mecab::with_model(&model).unwrap();
+        mecab
+            .load(
+                Utf8Path::new(std::env!("CARGO_MANIFEST_DIR"))
+                    .join("src/mecab/testdata/mecab_load"),
+            )
+            .unwrap();
+        drop(mecab);
+        assert!(Mecab::with_model(&model)
+            .unwrap()
+            .get_feature()
+            .unwrap()
+            .is_none());
+    }
+}
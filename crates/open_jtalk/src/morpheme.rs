@@ -0,0 +1,118 @@
+use super::*;
+
+/// MeCabの素性(`表層形,品詞,品詞細分類1,品詞細分類2,品詞細分類3,活用型,活用形,原形,読み,発音,アクセント型/モーラ数,アクセント結合規則`)を分解したもの。
+///
+/// 欠けているフィールドは空文字列、数値として解釈できないフィールドは0になる(`mecab2njd`と同様)。
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Morpheme {
+    pub string: String,
+    pub pos: String,
+    pub pos_group1: String,
+    pub pos_group2: String,
+    pub pos_group3: String,
+    pub ctype: String,
+    pub cform: String,
+    pub orig: String,
+    pub read: String,
+    pub pron: String,
+    pub acc: i32,
+    pub mora_size: i32,
+    pub chain_rule: String,
+}
+
+impl Morpheme {
+    pub fn parse(feature: &str) -> Self {
+        let mut fields = feature.split(',');
+        let mut next = || fields.next().unwrap_or_default().to_owned();
+        let string = next();
+        let pos = next();
+        let pos_group1 = next();
+        let pos_group2 = next();
+        let pos_group3 = next();
+        let ctype = next();
+        let cform = next();
+        let orig = next();
+        let read = next();
+        let pron = next();
+        let acc_and_mora_size = next();
+        let chain_rule = next();
+        let (acc, mora_size) = acc_and_mora_size
+            .split_once('/')
+            .unwrap_or((&acc_and_mora_size, ""));
+        Self {
+            string,
+            pos,
+            pos_group1,
+            pos_group2,
+            pos_group3,
+            ctype,
+            cform,
+            orig,
+            read,
+            pron,
+            acc: atoi(acc),
+            mora_size: atoi(mora_size),
+            chain_rule,
+        }
+    }
+}
+
+impl MecabFeatures<'_> {
+    pub fn morphemes(&self) -> Vec<Morpheme> {
+        self.iter()
+            .map(|feature| Morpheme::parse(&feature.to_string_lossy()))
+            .collect()
+    }
+}
+
+/// Cの`atoi`と同じく、先頭の整数部分のみを解釈する。
+fn atoi(s: &str) -> i32 {
+    let s = s.trim_start();
+    let (sign, digits) = match s.strip_prefix('-') {
+        Some(rest) => (-1, rest),
+        None => (1, s.strip_prefix('+').unwrap_or(s)),
+    };
+    digits
+        .chars()
+        .map_while(|c| c.to_digit(10))
+        .fold(0i32, |n, d| n.wrapping_mul(10).wrapping_add(d as i32))
+        .wrapping_mul(sign)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[rstest]
+    fn morpheme_parse_works() {
+        assert_eq!(
+            Morpheme {
+                string: "こんにちは".to_owned(),
+                pos: "感動詞".to_owned(),
+                pos_group1: "*".to_owned(),
+                pos_group2: "*".to_owned(),
+                pos_group3: "*".to_owned(),
+                ctype: "*".to_owned(),
+                cform: "*".to_owned(),
+                orig: "こんにちは".to_owned(),
+                read: "コンニチハ".to_owned(),
+                pron: "コンニチワ".to_owned(),
+                acc: 0,
+                mora_size: 5,
+                chain_rule: "C0".to_owned(),
+            },
+            Morpheme::parse("こんにちは,感動詞,*,*,*,*,*,こんにちは,コンニチハ,コンニチワ,0/5,C0")
+        );
+    }
+
+    #[rstest]
+    #[case("", 0, 0)]
+    #[case("x,x,x,x,x,x,x,x,x,x,*/*,*", 0, 0)]
+    #[case("x,x,x,x,x,x,x,x,x,x,-1/3", -1, 3)]
+    fn morpheme_parse_is_lenient(#[case] feature: &str, #[case] acc: i32, #[case] mora_size: i32) {
+        let morpheme = Morpheme::parse(feature);
+        assert_eq!((acc, mora_size), (morpheme.acc, morpheme.mora_size));
+    }
+}
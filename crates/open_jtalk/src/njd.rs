@@ -1,76 +1,329 @@
 use super::*;
-use std::{mem::MaybeUninit, os::raw::c_char};
+use std::{
+    ffi::{CStr, CString},
+    fmt,
+    io::{Read, Write},
+    os::raw::c_char,
+};
 
-#[derive(Default)]
-pub struct Njd(Option<open_jtalk_sys::NJD>);
+/// NJD(形態素解析結果に読みやアクセントなどを付与したもの)。
+///
+/// C側の構造体はヒープ上に確保するので、値を移動してもノードのリストは壊れない。
+pub struct Njd(RawBox<open_jtalk_sys::NJD>);
+
+/// NJDのノードの内容を複製したもの。
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NjdNode {
+    pub string: String,
+    pub pos: String,
+    pub pos_group1: String,
+    pub pos_group2: String,
+    pub pos_group3: String,
+    pub ctype: String,
+    pub cform: String,
+    pub orig: String,
+    pub read: String,
+    pub pron: String,
+    pub acc: i32,
+    pub mora_size: i32,
+    pub chain_rule: String,
+    pub chain_flag: i32,
+}
 
 unsafe impl resources::Resource for Njd {
     unsafe fn initialize(&mut self) -> bool {
-        if self.0.is_some() {
-            panic!("njd already initialized");
+        if self.0.is_initialized() {
+            return false;
         }
-        let njd = {
-            let mut njd = MaybeUninit::<open_jtalk_sys::NJD>::uninit();
-            open_jtalk_sys::NJD_initialize(njd.as_mut_ptr());
-            njd.assume_init()
-        };
-        self.0 = Some(njd);
+        open_jtalk_sys::NJD_initialize(self.0.as_uninit_ptr());
+        self.0.set_initialized(true);
         true
     }
     unsafe fn clear(&mut self) -> bool {
-        open_jtalk_sys::NJD_clear(self.as_raw_ptr());
-        self.0 = None;
+        let Ok(njd) = self.as_raw_ptr() else {
+            return false;
+        };
+        open_jtalk_sys::NJD_clear(njd);
+        self.0.set_initialized(false);
         true
     }
 }
 
+impl Drop for Njd {
+    fn drop(&mut self) {
+        unsafe {
+            resources::Resource::clear(self);
+        }
+    }
+}
+
 // SAFETY: `Send`と対立する性質はないはず。
 unsafe impl Send for Njd {}
 
+impl fmt::Debug for Njd {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Njd")
+            .field("nodes", &self.nodes().ok())
+            .finish()
+    }
+}
+
 impl Njd {
-    pub(crate) unsafe fn as_raw_ptr(&self) -> *mut open_jtalk_sys::NJD {
-        if self.0.is_none() {
-            panic!("uninitialized njd");
+    pub fn new() -> Result<Self> {
+        let mut njd = Self(RawBox::new());
+        if !unsafe { resources::Resource::initialize(&mut njd) } {
+            return Err(Error::Initialize { resource: "njd" });
         }
-        self.0.as_ref().unwrap() as *const open_jtalk_sys::NJD as *mut open_jtalk_sys::NJD
+        Ok(njd)
+    }
+
+    pub(crate) unsafe fn as_raw_ptr(&self) -> Result<*mut open_jtalk_sys::NJD> {
+        self.0.get().ok_or(Error::Uninitialized { resource: "njd" })
+    }
+
+    /// `NJD_fprint`と同じ形式(1行に1ノード)の文字列を返す。
+    ///
+    /// [`Njd::nodes`]から作るので、C側で`(null)`と書かれるNULLの文字列は空になる。
+    pub fn to_debug_string(&self) -> Result<String> {
+        Ok(self
+            .nodes()?
+            .iter()
+            .map(|node| node.to_line() + "\n")
+            .collect())
+    }
+
+    /// `NJD_fprint`と同じ形式(1行に1ノード)で書き出す。
+    pub fn write_to(&self, mut writer: impl Write) -> Result<()> {
+        writer.write_all(self.to_debug_string()?.as_bytes())?;
+        Ok(())
+    }
+
+    /// [`Njd::write_to`]の形式の文字列を読み込み、現在のノードを置き換える。空行は無視する。
+    ///
+    /// 解釈できない行があれば[`Error::Parse`]を返し、現在のノードは変更しない。
+    pub fn load_from_str(&mut self, s: &str) -> Result<()> {
+        let nodes = s
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(NjdNode::parse_line)
+            .collect::<Result<Vec<_>>>()?;
+        self.set_nodes(&nodes)
     }
 
-    pub fn set_pronunciation(&mut self) {
-        unsafe { open_jtalk_sys::njd_set_pronunciation(self.as_raw_ptr()) }
+    /// [`Njd::load_from_str`]と同じだが、`reader`から読み込む。
+    pub fn load_from_reader(&mut self, mut reader: impl Read) -> Result<()> {
+        let mut s = String::new();
+        reader.read_to_string(&mut s)?;
+        self.load_from_str(&s)
     }
 
-    pub fn set_digit(&mut self) {
-        unsafe { open_jtalk_sys::njd_set_digit(self.as_raw_ptr()) }
+    pub fn set_pronunciation(&mut self) -> Result<()> {
+        unsafe { open_jtalk_sys::njd_set_pronunciation(self.as_raw_ptr()?) }
+        Ok(())
     }
 
-    pub fn set_accent_type(&mut self) {
-        unsafe { open_jtalk_sys::njd_set_accent_type(self.as_raw_ptr()) }
+    pub fn set_digit(&mut self) -> Result<()> {
+        unsafe { open_jtalk_sys::njd_set_digit(self.as_raw_ptr()?) }
+        Ok(())
     }
 
-    pub fn set_accent_phrase(&mut self) {
-        unsafe { open_jtalk_sys::njd_set_accent_phrase(self.as_raw_ptr()) }
+    pub fn set_accent_type(&mut self) -> Result<()> {
+        unsafe { open_jtalk_sys::njd_set_accent_type(self.as_raw_ptr()?) }
+        Ok(())
     }
 
-    pub fn set_unvoiced_vowel(&mut self) {
-        unsafe { open_jtalk_sys::njd_set_unvoiced_vowel(self.as_raw_ptr()) }
+    pub fn set_accent_phrase(&mut self) -> Result<()> {
+        unsafe { open_jtalk_sys::njd_set_accent_phrase(self.as_raw_ptr()?) }
+        Ok(())
     }
 
-    pub fn set_long_vowel(&mut self) {
-        unsafe { open_jtalk_sys::njd_set_long_vowel(self.as_raw_ptr()) }
+    pub fn set_unvoiced_vowel(&mut self) -> Result<()> {
+        unsafe { open_jtalk_sys::njd_set_unvoiced_vowel(self.as_raw_ptr()?) }
+        Ok(())
     }
 
-    pub fn refresh(&mut self) {
-        unsafe { open_jtalk_sys::NJD_refresh(self.as_raw_ptr()) }
+    pub fn set_long_vowel(&mut self) -> Result<()> {
+        unsafe { open_jtalk_sys::njd_set_long_vowel(self.as_raw_ptr()?) }
+        Ok(())
+    }
+
+    pub fn refresh(&mut self) -> Result<()> {
+        unsafe { open_jtalk_sys::NJD_refresh(self.as_raw_ptr()?) }
+        Ok(())
+    }
+
+    pub fn nodes(&self) -> Result<Vec<NjdNode>> {
+        let mut nodes = vec![];
+        unsafe {
+            let mut node = (*self.as_raw_ptr()?).head;
+            while !node.is_null() {
+                nodes.push(NjdNode::from_raw(&*node));
+                node = (*node).next;
+            }
+        }
+        Ok(nodes)
     }
 
-    pub fn mecab2njd(&mut self, mecab_feature: &MecabFeature, mecab_feature_size: i32) {
+    /// ノードをすべて`nodes`で置き換える。失敗した場合、ノードは変更しない。
+    pub fn set_nodes(&mut self, nodes: &[NjdNode]) -> Result<()> {
+        // NULを含む文字列があれば、NJDを変更する前に失敗させる。
+        let strings = nodes
+            .iter()
+            .map(NjdNode::to_c_strings)
+            .collect::<Result<Vec<_>>>()?;
+        unsafe {
+            let njd = self.as_raw_ptr()?;
+            let mut raws = Vec::with_capacity(nodes.len());
+            for (node, strings) in nodes.iter().zip(&strings) {
+                // `NJD_refresh`などで`free`されるので、`malloc`で確保する。
+                let raw = libc::malloc(std::mem::size_of::<open_jtalk_sys::NJDNode>())
+                    .cast::<open_jtalk_sys::NJDNode>();
+                if raw.is_null() {
+                    for raw in raws {
+                        open_jtalk_sys::NJDNode_clear(raw);
+                        libc::free(raw.cast());
+                    }
+                    return Err(Error::Unsuccessful { function: "malloc" });
+                }
+                open_jtalk_sys::NJDNode_initialize(raw);
+                for (set, s) in NJD_NODE_STRING_SETTERS.iter().zip(strings) {
+                    set(raw, s.as_ptr());
+                }
+                open_jtalk_sys::NJDNode_set_acc(raw, node.acc);
+                open_jtalk_sys::NJDNode_set_mora_size(raw, node.mora_size);
+                open_jtalk_sys::NJDNode_set_chain_flag(raw, node.chain_flag);
+                raws.push(raw);
+            }
+            // すべてのノードを確保できてから置き換える
+            open_jtalk_sys::NJD_refresh(njd);
+            for raw in raws {
+                open_jtalk_sys::NJD_push_node(njd, raw);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn mecab2njd(&mut self, mecab_features: MecabFeatures<'_>) -> Result<()> {
         unsafe {
             open_jtalk_sys::mecab2njd(
-                self.as_raw_ptr(),
-                mecab_feature as *const MecabFeature as *mut *mut c_char,
-                mecab_feature_size,
+                self.as_raw_ptr()?,
+                mecab_features.as_ptr(),
+                mecab_features.len() as i32,
             )
         }
+        Ok(())
+    }
+}
+
+/// [`NjdNode::to_c_strings`]の順に並べた、文字列のフィールドの`NJDNode_set_*`。
+const NJD_NODE_STRING_SETTERS: [unsafe extern "C" fn(*mut open_jtalk_sys::NJDNode, *const c_char);
+    11] = [
+    open_jtalk_sys::NJDNode_set_string,
+    open_jtalk_sys::NJDNode_set_pos,
+    open_jtalk_sys::NJDNode_set_pos_group1,
+    open_jtalk_sys::NJDNode_set_pos_group2,
+    open_jtalk_sys::NJDNode_set_pos_group3,
+    open_jtalk_sys::NJDNode_set_ctype,
+    open_jtalk_sys::NJDNode_set_cform,
+    open_jtalk_sys::NJDNode_set_orig,
+    open_jtalk_sys::NJDNode_set_read,
+    open_jtalk_sys::NJDNode_set_pron,
+    open_jtalk_sys::NJDNode_set_chain_rule,
+];
+
+impl NjdNode {
+    /// `NJD_fprint`の1行(`string,pos,…,pron,acc/mora_size,chain_rule,chain_flag`)を解釈する。
+    fn parse_line(line: &str) -> Result<Self> {
+        let error = || Error::Parse {
+            what: "NJD node",
+            input: line.to_owned(),
+        };
+        let fields = line.split(',').collect::<Vec<_>>();
+        let [string, pos, pos_group1, pos_group2, pos_group3, ctype, cform, orig, read, pron, acc_and_mora_size, chain_rule, chain_flag] =
+            *fields
+        else {
+            return Err(error());
+        };
+        let (acc, mora_size) = acc_and_mora_size.split_once('/').ok_or_else(error)?;
+        Ok(Self {
+            string: string.to_owned(),
+            pos: pos.to_owned(),
+            pos_group1: pos_group1.to_owned(),
+            pos_group2: pos_group2.to_owned(),
+            pos_group3: pos_group3.to_owned(),
+            ctype: ctype.to_owned(),
+            cform: cform.to_owned(),
+            orig: orig.to_owned(),
+            read: read.to_owned(),
+            pron: pron.to_owned(),
+            acc: acc.parse().map_err(|_| error())?,
+            mora_size: mora_size.parse().map_err(|_| error())?,
+            chain_rule: chain_rule.to_owned(),
+            chain_flag: chain_flag.parse().map_err(|_| error())?,
+        })
+    }
+
+    /// `NJDNode_sprint`と同じく、フィールドを`,`で区切った1行(改行を含まない)にする。
+    fn to_line(&self) -> String {
+        format!(
+            "{},{},{},{},{},{},{},{},{},{},{}/{},{},{}",
+            self.string,
+            self.pos,
+            self.pos_group1,
+            self.pos_group2,
+            self.pos_group3,
+            self.ctype,
+            self.cform,
+            self.orig,
+            self.read,
+            self.pron,
+            self.acc,
+            self.mora_size,
+            self.chain_rule,
+            self.chain_flag,
+        )
+    }
+
+    fn to_c_strings(&self) -> Result<[CString; 11]> {
+        Ok([
+            CString::new(&*self.string)?,
+            CString::new(&*self.pos)?,
+            CString::new(&*self.pos_group1)?,
+            CString::new(&*self.pos_group2)?,
+            CString::new(&*self.pos_group3)?,
+            CString::new(&*self.ctype)?,
+            CString::new(&*self.cform)?,
+            CString::new(&*self.orig)?,
+            CString::new(&*self.read)?,
+            CString::new(&*self.pron)?,
+            CString::new(&*self.chain_rule)?,
+        ])
+    }
+
+    unsafe fn from_raw(node: &open_jtalk_sys::NJDNode) -> Self {
+        unsafe fn to_string(s: *const c_char) -> String {
+            if s.is_null() {
+                return String::new();
+            }
+            CStr::from_ptr(s).to_string_lossy().into_owned()
+        }
+        Self {
+            string: to_string(node.string),
+            pos: to_string(node.pos),
+            pos_group1: to_string(node.pos_group1),
+            pos_group2: to_string(node.pos_group2),
+            pos_group3: to_string(node.pos_group3),
+            ctype: to_string(node.ctype),
+            cform: to_string(node.cform),
+            orig: to_string(node.orig),
+            read: to_string(node.read),
+            pron: to_string(node.pron),
+            acc: node.acc,
+            mora_size: node.mora_size,
+            chain_rule: to_string(node.chain_rule),
+            chain_flag: node.chain_flag,
+        }
     }
 }
 
@@ -81,57 +334,103 @@ mod tests {
     use resources::Resource as _;
     #[rstest]
     fn njd_initialize_and_clear_works() {
-        let mut njd = Njd::default();
+        let mut njd = Njd::new().unwrap();
         unsafe {
-            assert!(njd.initialize());
+            assert!(!njd.initialize());
             assert!(njd.clear());
+            assert!(!njd.clear());
+            assert!(njd.initialize());
+        }
+    }
+
+    #[rstest]
+    fn njd_cleared_returns_error() {
+        let mut njd = Njd::new().unwrap();
+        unsafe {
+            njd.clear();
         }
+        assert!(matches!(
+            njd.set_digit(),
+            Err(Error::Uninitialized { resource: "njd" })
+        ));
+    }
+
+    #[rstest]
+    fn njd_set_nodes_works() {
+        let mut njd = Njd::new().unwrap();
+        let nodes = vec![
+            NjdNode {
+                string: "こんにちは".to_owned(),
+                pos: "感動詞".to_owned(),
+                pron: "コンニチワ".to_owned(),
+                acc: 0,
+                mora_size: 5,
+                chain_flag: -1,
+                ..Default::default()
+            },
+            NjdNode {
+                string: "。".to_owned(),
+                pos: "記号".to_owned(),
+                chain_flag: -1,
+                ..Default::default()
+            },
+        ];
+        njd.set_nodes(&nodes).unwrap();
+        assert_eq!(nodes, njd.nodes().unwrap());
+        assert!(matches!(
+            njd.set_nodes(&[NjdNode {
+                string: "a\0b".to_owned(),
+                ..Default::default()
+            }]),
+            Err(Error::Nul { position: 1 })
+        ));
+        assert_eq!(nodes, njd.nodes().unwrap());
     }
 
     #[rstest]
     fn njd_set_pronunciation_works() {
-        let mut njd = ManagedResource::<Njd>::initialize();
-        njd.set_pronunciation();
+        let mut njd = Njd::new().unwrap();
+        njd.set_pronunciation().unwrap();
     }
 
     #[rstest]
     fn njd_set_digit_works() {
-        let mut njd = ManagedResource::<Njd>::initialize();
-        njd.set_digit();
+        let mut njd = Njd::new().unwrap();
+        njd.set_digit().unwrap();
     }
 
     #[rstest]
     fn njd_set_accent_type_works() {
-        let mut njd = ManagedResource::<Njd>::initialize();
-        njd.set_accent_type();
+        let mut njd = Njd::new().unwrap();
+        njd.set_accent_type().unwrap();
     }
 
     #[rstest]
     fn njd_set_accent_phrase_works() {
-        let mut njd = ManagedResource::<Njd>::initialize();
-        njd.set_accent_phrase();
+        let mut njd = Njd::new().unwrap();
+        njd.set_accent_phrase().unwrap();
     }
 
     #[rstest]
     fn njd_set_unvoiced_vowel_works() {
-        let mut njd = ManagedResource::<Njd>::initialize();
-        njd.set_unvoiced_vowel();
+        let mut njd = Njd::new().unwrap();
+        njd.set_unvoiced_vowel().unwrap();
     }
     #[rstest]
     fn njd_set_long_vowel_works() {
-        let mut njd = ManagedResource::<Njd>::initialize();
-        njd.set_long_vowel();
+        let mut njd = Njd::new().unwrap();
+        njd.set_long_vowel().unwrap();
     }
     #[rstest]
     fn njd_refresh_works() {
-        let mut njd = ManagedResource::<Njd>::initialize();
-        njd.refresh();
+        let mut njd = Njd::new().unwrap();
+        njd.refresh().unwrap();
     }
 
     #[rstest]
     fn njd_mecab2njd_works() {
-        let mut njd = ManagedResource::<Njd>::initialize();
-        let mut mecab = ManagedResource::<Mecab>::initialize();
+        let mut njd = Njd::new().unwrap();
+        let mut mecab = Mecab::new().unwrap();
 
         mecab
             .load(
@@ -140,7 +439,134 @@ mod tests {
             )
             .unwrap();
         let s = text2mecab("h^o-d+e=s/A:2+3+2/B:22-xx_xx/C:10_7+2/D:xx+xx_xx/E:5_5!0_xx-0/F:4_1#0_xx@1_1|1_4/G:xx_xx%xx_xx_xx/H:1_5/I:1-4@2+1&2-1|6+4/J:xx_xx/K:2+2-9").unwrap();
-        assert!(mecab.analysis(s));
-        njd.mecab2njd(mecab.get_feature().unwrap(), mecab.get_size());
+        mecab.analysis(s).unwrap();
+        njd.mecab2njd(mecab.get_feature().unwrap().unwrap())
+            .unwrap();
+    }
+
+    #[rstest]
+    fn njd_to_debug_string_works() {
+        let mut njd = Njd::new().unwrap();
+        assert_eq!("", njd.to_debug_string().unwrap());
+
+        let mut mecab = Mecab::new().unwrap();
+        mecab
+            .load(
+                Utf8Path::new(std::env!("CARGO_MANIFEST_DIR"))
+                    .join("src/mecab/testdata/mecab_load"),
+            )
+            .unwrap();
+        mecab.analysis(text2mecab("こんにちは").unwrap()).unwrap();
+        njd.mecab2njd(mecab.get_feature().unwrap().unwrap())
+            .unwrap();
+        let dump = njd.to_debug_string().unwrap();
+        assert_eq!(njd.nodes().unwrap().len(), dump.lines().count());
+        assert!(dump.contains("こんにちは"));
+        assert!(format!("{njd:?}").contains("こんにちは"));
+    }
+
+    #[rstest]
+    fn njd_write_to_and_load_from_str_round_trip() {
+        let mut mecab = Mecab::new().unwrap();
+        mecab
+            .load(
+                Utf8Path::new(std::env!("CARGO_MANIFEST_DIR"))
+                    .join("src/mecab/testdata/mecab_load"),
+            )
+            .unwrap();
+        mecab.analysis(text2mecab("こんにちは").unwrap()).unwrap();
+        let mut njd = Njd::new().unwrap();
+        njd.mecab2njd(mecab.get_feature().unwrap().unwrap())
+            .unwrap();
+        njd.set_pronunciation().unwrap();
+
+        let mut saved = vec![];
+        njd.write_to(&mut saved).unwrap();
+        let mut loaded = Njd::new().unwrap();
+        loaded.load_from_reader(&*saved).unwrap();
+        assert_eq!(njd.nodes().unwrap(), loaded.nodes().unwrap());
+
+        // 読み込み直すと置き換わる
+        loaded
+            .load_from_str(std::str::from_utf8(&saved).unwrap())
+            .unwrap();
+        assert_eq!(njd.nodes().unwrap(), loaded.nodes().unwrap());
+
+        // 解釈できない行があれば、ノードは変更しない
+        assert!(matches!(
+            loaded.load_from_str("こんにちは,感動詞\n"),
+            Err(Error::Parse { .. })
+        ));
+        assert_eq!(njd.nodes().unwrap(), loaded.nodes().unwrap());
+
+        let mut jpcommon = JpCommon::new().unwrap();
+        jpcommon.njd2jpcommon(&loaded).unwrap();
+        jpcommon.make_label().unwrap();
+        assert!(jpcommon.get_label_size().unwrap() > 2);
+    }
+
+    #[rstest]
+    fn njd_node_to_line_round_trips() {
+        let line = "こんにちは,感動詞,*,*,*,*,*,こんにちは,コンニチハ,コンニチワ,0/5,C1,-1";
+        assert_eq!(line, NjdNode::parse_line(line).unwrap().to_line());
+    }
+
+    #[rstest]
+    fn njd_node_parse_line_works() {
+        let line = "こんにちは,感動詞,*,*,*,*,*,こんにちは,コンニチハ,コンニチワ,0/5,C1,-1";
+        assert_eq!(
+            NjdNode {
+                string: "こんにちは".to_owned(),
+                pos: "感動詞".to_owned(),
+                pos_group1: "*".to_owned(),
+                pos_group2: "*".to_owned(),
+                pos_group3: "*".to_owned(),
+                ctype: "*".to_owned(),
+                cform: "*".to_owned(),
+                orig: "こんにちは".to_owned(),
+                read: "コンニチハ".to_owned(),
+                pron: "コンニチワ".to_owned(),
+                acc: 0,
+                mora_size: 5,
+                chain_rule: "C1".to_owned(),
+                chain_flag: -1,
+            },
+            NjdNode::parse_line(line).unwrap()
+        );
+    }
+
+    #[rstest]
+    #[case("こんにちは,感動詞")]
+    #[case("こんにちは,感動詞,*,*,*,*,*,こんにちは,コンニチハ,コンニチワ,0,C1,-1")]
+    #[case("こんにちは,感動詞,*,*,*,*,*,こんにちは,コンニチハ,コンニチワ,0/5,C1,x")]
+    #[case("こんにちは,感動詞,*,*,*,*,*,こんにちは,コンニチハ,コンニチワ,0/5,C1,-1,*")]
+    fn njd_node_parse_line_rejects_malformed_lines(#[case] line: &str) {
+        assert!(matches!(
+            NjdNode::parse_line(line),
+            Err(Error::Parse { .. })
+        ));
+    }
+
+    #[rstest]
+    fn njd_nodes_works() {
+        let mut njd = Njd::new().unwrap();
+        assert_eq!(Vec::<NjdNode>::new(), njd.nodes().unwrap());
+
+        let mut mecab = Mecab::new().unwrap();
+        mecab
+            .load(
+                Utf8Path::new(std::env!("CARGO_MANIFEST_DIR"))
+                    .join("src/mecab/testdata/mecab_load"),
+            )
+            .unwrap();
+        mecab.analysis(text2mecab("こんにちは").unwrap()).unwrap();
+        njd.mecab2njd(mecab.get_feature().unwrap().unwrap())
+            .unwrap();
+        let nodes = njd.nodes().unwrap();
+        assert!(!nodes.is_empty());
+        assert_eq!(
+            "こんにちは",
+            nodes.iter().map(|n| &*n.string).collect::<String>()
+        );
     }
 }
@@ -0,0 +1,217 @@
+use std::{
+    fmt,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use super::*;
+
+/// Rustで書かれた、NJDを書き換える処理。
+///
+/// [`NjdPassRegistry`]に登録し、NJDの段階(`Njd::set_accent_type`など)の代わりや後に実行する。
+/// クロージャ`FnMut(&mut Njd) -> Result<()>`もこれを実装する。
+pub trait NjdPass: Send + 'static {
+    fn apply(&mut self, njd: &mut Njd) -> Result<()>;
+}
+
+impl<F: FnMut(&mut Njd) -> Result<()> + Send + 'static> NjdPass for F {
+    fn apply(&mut self, njd: &mut Njd) -> Result<()> {
+        self(njd)
+    }
+}
+
+impl NjdPass for NumberReader {
+    fn apply(&mut self, njd: &mut Njd) -> Result<()> {
+        NumberReader::apply(self, njd)
+    }
+}
+
+impl NjdPass for EnglishReader {
+    fn apply(&mut self, njd: &mut Njd) -> Result<()> {
+        EnglishReader::apply(self, njd)
+    }
+}
+
+/// [`NjdPass`]を実行する位置。
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Placement {
+    /// 段階を無効にし、代わりに実行する。
+    Replace,
+    /// 段階の後に実行する。
+    After,
+}
+
+/// 名前を付けた[`NjdPass`]の集まり。[`PipelineBuilder::passes`]でパイプラインに組み込む。
+///
+/// ```
+/// use open_jtalk::{Njd, NjdNode, NjdPassRegistry, Pipeline, Stage};
+///
+/// // 東京式のアクセント規則の代わりに、すべて平板型にする
+/// let registry = NjdPassRegistry::new().replace("flat", Stage::SetAccentType, |njd: &mut Njd| {
+///     let nodes = njd
+///         .nodes()?
+///         .into_iter()
+///         .map(|node| NjdNode { acc: 0, ..node })
+///         .collect::<Vec<_>>();
+///     njd.set_nodes(&nodes)
+/// });
+/// let pipeline = Pipeline::builder().passes(&registry).build().unwrap();
+/// assert!(!pipeline.is_enabled(Stage::SetAccentType));
+/// ```
+#[derive(Clone, Default)]
+pub struct NjdPassRegistry {
+    passes: Vec<RegisteredPass>,
+}
+
+#[derive(Clone)]
+struct RegisteredPass {
+    name: String,
+    stage: Stage,
+    placement: Placement,
+    pass: Arc<Mutex<dyn NjdPass>>,
+}
+
+impl NjdPassRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `stage`を無効にし、代わりに`pass`を実行するよう登録する。
+    pub fn replace(self, name: impl Into<String>, stage: Stage, pass: impl NjdPass) -> Self {
+        self.register(name, stage, Placement::Replace, pass)
+    }
+
+    /// `stage`の後に`pass`を実行するよう登録する。
+    pub fn after(self, name: impl Into<String>, stage: Stage, pass: impl NjdPass) -> Self {
+        self.register(name, stage, Placement::After, pass)
+    }
+
+    /// `pass`を登録する。同じ名前のものがあれば置き換える。同じ位置に登録したものは登録した順に実行する。
+    pub fn register(
+        mut self,
+        name: impl Into<String>,
+        stage: Stage,
+        placement: Placement,
+        pass: impl NjdPass,
+    ) -> Self {
+        let name = name.into();
+        self.remove(&name);
+        self.passes.push(RegisteredPass {
+            name,
+            stage,
+            placement,
+            pass: Arc::new(Mutex::new(pass)),
+        });
+        self
+    }
+
+    /// 名前が`name`のものを取り除く。取り除いたかどうかを返す。
+    pub fn remove(&mut self, name: &str) -> bool {
+        let len = self.passes.len();
+        self.passes.retain(|p| p.name != name);
+        self.passes.len() != len
+    }
+
+    /// 登録されているものの名前と位置。
+    pub fn entries(&self) -> impl Iterator<Item = (&str, Stage, Placement)> {
+        self.passes.iter().map(|p| (&*p.name, p.stage, p.placement))
+    }
+}
+
+impl fmt::Debug for NjdPassRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.entries()).finish()
+    }
+}
+
+impl PipelineBuilder {
+    /// `registry`に登録された[`NjdPass`]を組み込む。
+    ///
+    /// 登録したオブジェクトはこのパイプライン(とその複製)の間で共有され、同時には1つずつ実行される。
+    pub fn passes(self, registry: &NjdPassRegistry) -> Self {
+        registry.passes.iter().fold(self, |builder, p| {
+            let pass = p.pass.clone();
+            let hook = move |njd: &mut Njd| {
+                pass.lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .apply(njd)
+            };
+            match p.placement {
+                Placement::Replace => builder.replace(p.stage, hook),
+                Placement::After => builder.hook_after(p.stage, hook),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[rstest]
+    fn njd_pass_registry_works() {
+        let mut registry = NjdPassRegistry::new()
+            .replace("kansai", Stage::SetAccentType, |_: &mut Njd| Ok(()))
+            .after("english", Stage::Mecab2Njd, EnglishReader::default())
+            .after("compound", Stage::SetAccentType, |_: &mut Njd| Ok(()))
+            .replace("digit", Stage::SetDigit, NumberReader::default())
+            .after("kansai", Stage::SetAccentType, |_: &mut Njd| Ok(()));
+        assert_eq!(
+            vec![
+                ("english", Stage::Mecab2Njd, Placement::After),
+                ("compound", Stage::SetAccentType, Placement::After),
+                ("digit", Stage::SetDigit, Placement::Replace),
+                ("kansai", Stage::SetAccentType, Placement::After),
+            ],
+            registry.entries().collect::<Vec<_>>()
+        );
+        assert!(registry.remove("digit"));
+        assert!(!registry.remove("digit"));
+
+        let pipeline = Pipeline::builder().passes(&registry).build().unwrap();
+        assert!(pipeline.is_enabled(Stage::SetDigit));
+        assert!(pipeline.is_enabled(Stage::SetAccentType));
+    }
+
+    #[rstest]
+    fn njd_pass_replaces_set_accent_type() {
+        struct Flat {
+            calls: Arc<Mutex<usize>>,
+        }
+
+        impl NjdPass for Flat {
+            fn apply(&mut self, njd: &mut Njd) -> Result<()> {
+                *self.calls.lock().unwrap() += 1;
+                let nodes = njd
+                    .nodes()?
+                    .into_iter()
+                    .map(|node| NjdNode { acc: 0, ..node })
+                    .collect::<Vec<_>>();
+                njd.set_nodes(&nodes)
+            }
+        }
+
+        let calls = Arc::new(Mutex::new(0));
+        let registry = NjdPassRegistry::new().replace(
+            "flat",
+            Stage::SetAccentType,
+            Flat {
+                calls: calls.clone(),
+            },
+        );
+        let pipeline = Pipeline::builder().passes(&registry).build().unwrap();
+        assert!(!pipeline.is_enabled(Stage::SetAccentType));
+        let mut open_jtalk = OpenJtalk::new(
+            camino::Utf8Path::new(std::env!("CARGO_MANIFEST_DIR"))
+                .join("src/mecab/testdata/mecab_load"),
+        )
+        .unwrap()
+        .with_pipeline(pipeline);
+        let labels = open_jtalk.extract_fullcontext("こんにちは").unwrap();
+        assert_eq!(1, *calls.lock().unwrap());
+        for label in &labels[1..labels.len() - 1] {
+            let label = label.parse::<FullContextLabel>().unwrap();
+            assert_eq!(Some(0), label.f[1]);
+        }
+    }
+}
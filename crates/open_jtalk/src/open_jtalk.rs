@@ -0,0 +1,469 @@
+use super::*;
+use camino::Utf8Path;
+use std::{ops::Range, sync::Arc};
+
+/// text2mecabからラベル生成までの一連の処理を行う。
+pub struct OpenJtalk {
+    model: MecabModel,
+    mecab: Mecab,
+    njd: Njd,
+    jpcommon: JpCommon,
+    pipeline: Pipeline,
+}
+
+/// 文ごとに生成したフルコンテキストラベル。
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SentenceFullcontext {
+    /// 元の文字列上のバイト範囲。
+    pub range: Range<usize>,
+    /// 元の文字列上の文字(`char`)単位の範囲。
+    pub char_range: Range<usize>,
+    pub labels: Vec<String>,
+}
+
+impl OpenJtalk {
+    pub fn new(dic_dir: impl AsRef<Utf8Path>) -> Result<Self> {
+        Self::new_with_userdic(dic_dir.as_ref(), None)
+    }
+
+    pub fn new_with_userdic(dic_dir: &Utf8Path, userdic: Option<&Utf8Path>) -> Result<Self> {
+        Self::from_model(&MecabModel::load_with_userdic(dic_dir, userdic)?)
+    }
+
+    /// 読み込み済みの辞書を共有する`OpenJtalk`を作る。
+    pub fn from_model(model: &MecabModel) -> Result<Self> {
+        Ok(Self {
+            model: model.clone(),
+            mecab: Mecab::with_model(model)?,
+            njd: Njd::new()?,
+            jpcommon: JpCommon::new()?,
+            pipeline: Pipeline::default(),
+        })
+    }
+
+    pub fn with_pipeline(mut self, pipeline: Pipeline) -> Self {
+        self.pipeline = pipeline;
+        self
+    }
+
+    pub fn model(&self) -> &MecabModel {
+        &self.model
+    }
+
+    pub fn pipeline(&self) -> &Pipeline {
+        &self.pipeline
+    }
+
+    pub fn set_pipeline(&mut self, pipeline: Pipeline) {
+        self.pipeline = pipeline;
+    }
+
+    pub fn extract_fullcontext(&mut self, text: impl AsRef<str>) -> Result<Vec<String>> {
+        let result = self
+            .mecab_text(text.as_ref())
+            .and_then(|mecab_text| self.extract_fullcontext_non_refresh(&mecab_text, None));
+        self.finish(result)
+    }
+
+    /// [`OpenJtalk::extract_fullcontext`]と同じだが、各段階の間で`cancel`を確認し、
+    /// 中断が指示されていれば[`Error::Cancelled`]を返す。
+    pub fn extract_fullcontext_cancellable(
+        &mut self,
+        text: impl AsRef<str>,
+        cancel: &CancelFlag,
+    ) -> Result<Vec<String>> {
+        let result = cancel
+            .check()
+            .and_then(|()| self.mecab_text(text.as_ref()))
+            .and_then(|mecab_text| self.extract_fullcontext_non_refresh(&mecab_text, Some(cancel)));
+        self.finish(result)
+    }
+
+    /// フルコンテキストラベルに加えて、NJDのノード・アクセント句・モーラと入力上の範囲との対応を返す。
+    ///
+    /// 範囲はtext2mecabによる半角から全角への変換と、`njd_set_digit`による数字の読み替えを考慮して求める。
+    pub fn analyze(&mut self, text: &str) -> Result<Analysis> {
+        let converted = self.converted_text(text);
+        let result = self
+            .extract_fullcontext_non_refresh(&converted.text, None)
+            .and_then(|labels| {
+                let nodes = align_nodes(&converted, self.njd.nodes()?);
+                Ok(Analysis {
+                    morphemes: self
+                        .mecab
+                        .get_feature()?
+                        .map(|features| features.morphemes())
+                        .unwrap_or_default(),
+                    accent_phrases: AccentPhrase::from_aligned_nodes(&nodes),
+                    nodes,
+                    labels,
+                })
+            });
+        self.finish(result)
+    }
+
+    /// `overrides`で指定した範囲の読みとアクセントを上書きしてフルコンテキストラベルを生成する。
+    ///
+    /// 上書きは`set_accent_phrase`の直前に行うので、アクセント句の区切りや無声化は上書き後の読みに基づく。
+    /// 範囲と重なるノードは1つにまとめられる。
+    pub fn extract_fullcontext_with_overrides(
+        &mut self,
+        text: &str,
+        overrides: &[ReadingOverride],
+    ) -> Result<Vec<String>> {
+        let converted = Arc::new(self.converted_text(text));
+        let hook = {
+            let converted = converted.clone();
+            let overrides = overrides.to_vec();
+            Arc::new(move |njd: &mut Njd| {
+                let nodes = override_nodes(align_nodes(&converted, njd.nodes()?), &overrides)?;
+                njd.set_nodes(&nodes)
+            })
+        };
+        let pipeline = self.pipeline.clone();
+        self.pipeline = pipeline.with_hook_before(Stage::SetAccentPhrase, hook);
+        let result = self.extract_fullcontext_non_refresh(&converted.text, None);
+        self.pipeline = pipeline;
+        self.finish(result)
+    }
+
+    /// 内部状態をリセットしてから`result`を返す。`result`のエラーをリセットのエラーより優先する。
+    fn finish<T>(&mut self, result: Result<T>) -> Result<T> {
+        let refreshed = self.refresh();
+        let value = result?;
+        refreshed?;
+        Ok(value)
+    }
+
+    /// 韻律記号付きの音素列を生成する。[`ProsodySymbol::from_labels`]を参照。
+    pub fn extract_prosody(
+        &mut self,
+        text: impl AsRef<str>,
+        drop_unvoiced_vowels: bool,
+    ) -> Result<Vec<ProsodySymbol>> {
+        let labels = self
+            .extract_fullcontext(text)?
+            .iter()
+            .map(|label| label.parse())
+            .collect::<Result<Vec<_>>>()?;
+        Ok(ProsodySymbol::from_labels(&labels, drop_unvoiced_vowels))
+    }
+
+    /// 複数の文字列それぞれについてフルコンテキストラベルを生成する。結果は入力と同じ順に並ぶ。
+    ///
+    /// `rayon`フィーチャが有効な場合は、辞書を共有する`OpenJtalk`をrayonのスレッドごとに1つずつ、
+    /// 最初に使うときに作って並列に処理する。
+    /// その`OpenJtalk`の作成に失敗した場合、そのスレッドが受け持つ入力はすべてそのエラーになる。
+    pub fn extract_fullcontext_batch<S: AsRef<str> + Sync>(
+        &mut self,
+        texts: &[S],
+    ) -> Vec<Result<Vec<String>>> {
+        #[cfg(feature = "rayon")]
+        {
+            use rayon::prelude::*;
+            use std::sync::{Mutex, PoisonError};
+
+            let model = &self.model;
+            let pipeline = &self.pipeline;
+            let workers = (0..rayon::current_num_threads())
+                .map(|_| Mutex::new(None::<Result<Self>>))
+                .collect::<Vec<_>>();
+            texts
+                .par_iter()
+                .map(|text| {
+                    let index = rayon::current_thread_index().unwrap_or(0) % workers.len();
+                    let mut worker = workers[index]
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner);
+                    worker
+                        .get_or_insert_with(|| {
+                            Self::from_model(model).map(|o| o.with_pipeline(pipeline.clone()))
+                        })
+                        .as_mut()
+                        .map_err(|e| e.clone())?
+                        .extract_fullcontext(text)
+                })
+                .collect()
+        }
+        #[cfg(not(feature = "rayon"))]
+        {
+            texts
+                .iter()
+                .map(|text| self.extract_fullcontext(text))
+                .collect()
+        }
+    }
+
+    /// `text`を`splitter`で文に分割し、文ごとにフルコンテキストラベルを生成する。
+    pub fn extract_fullcontext_by_sentence(
+        &mut self,
+        text: &str,
+        splitter: &SentenceSplitter,
+    ) -> Result<Vec<SentenceFullcontext>> {
+        splitter
+            .split(text)
+            .into_iter()
+            .map(|sentence| {
+                Ok(SentenceFullcontext {
+                    labels: self.extract_fullcontext(sentence.text)?,
+                    range: sentence.range,
+                    char_range: sentence.char_range,
+                })
+            })
+            .collect()
+    }
+
+    fn mecab_text(&self, text: &str) -> Result<String> {
+        if !self.pipeline.uses_text2mecab() {
+            return Ok(text.to_owned());
+        }
+        Ok(text2mecab(text)?)
+    }
+
+    fn converted_text(&self, text: &str) -> ConvertedText {
+        if self.pipeline.uses_text2mecab() {
+            ConvertedText::new(&Text2Mecab::default(), text)
+        } else {
+            ConvertedText::identity(text)
+        }
+    }
+
+    pub(crate) fn refresh(&mut self) -> Result<()> {
+        self.jpcommon.refresh()?;
+        self.njd.refresh()?;
+        self.mecab.refresh()
+    }
+
+    fn extract_fullcontext_non_refresh(
+        &mut self,
+        mecab_text: &str,
+        cancel: Option<&CancelFlag>,
+    ) -> Result<Vec<String>> {
+        let check = || cancel.map_or(Ok(()), CancelFlag::check);
+        self.mecab.analysis(mecab_text)?;
+        check()?;
+        self.njd
+            .mecab2njd(self.mecab.get_feature()?.ok_or(Error::NoFeatures)?)?;
+        self.pipeline.run_njd(&mut self.njd, check)?;
+        if !self.pipeline.makes_labels() {
+            return Ok(vec![]);
+        }
+        check()?;
+        self.jpcommon.njd2jpcommon(&self.njd)?;
+        self.jpcommon.make_label()?;
+        check()?;
+        self.jpcommon
+            .get_label_feature_to_iter()?
+            .ok_or(Error::NoFeatures)?
+            .map(|label| label.map(ToOwned::to_owned))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use camino::Utf8Path;
+    use pretty_assertions::assert_eq;
+
+    fn open_jtalk() -> OpenJtalk {
+        OpenJtalk::new(
+            Utf8Path::new(std::env!("CARGO_MANIFEST_DIR")).join("src/mecab/testdata/mecab_load"),
+        )
+        .unwrap()
+    }
+
+    #[rstest]
+    fn open_jtalk_extract_fullcontext_works() {
+        let mut open_jtalk = open_jtalk();
+        let labels = open_jtalk.extract_fullcontext("こんにちは").unwrap();
+        assert!(labels.len() > 2);
+        assert!(labels[0].contains("-sil+"));
+        assert!(labels[labels.len() - 1].contains("-sil+"));
+
+        // 内部状態がリセットされているので、同じ入力には同じ結果を返す
+        assert_eq!(
+            labels,
+            open_jtalk.extract_fullcontext("こんにちは").unwrap()
+        );
+    }
+
+    #[rstest]
+    fn open_jtalk_can_be_moved_across_threads() {
+        let mut open_jtalks = vec![open_jtalk(), open_jtalk()];
+        let expected = open_jtalks[0].extract_fullcontext("こんにちは").unwrap();
+        let handles = open_jtalks
+            .drain(..)
+            .map(|mut open_jtalk| {
+                std::thread::spawn(move || open_jtalk.extract_fullcontext("こんにちは").unwrap())
+            })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            assert_eq!(expected, handle.join().unwrap());
+        }
+    }
+
+    #[rstest]
+    fn open_jtalk_extract_fullcontext_cancellable_works() {
+        let mut open_jtalk = open_jtalk();
+        let cancel = CancelFlag::new();
+        assert_eq!(
+            open_jtalk.extract_fullcontext("こんにちは").unwrap(),
+            open_jtalk
+                .extract_fullcontext_cancellable("こんにちは", &cancel)
+                .unwrap()
+        );
+        cancel.cancel();
+        assert!(matches!(
+            open_jtalk.extract_fullcontext_cancellable("こんにちは", &cancel),
+            Err(Error::Cancelled)
+        ));
+    }
+
+    #[rstest]
+    fn open_jtalk_extract_fullcontext_batch_works() {
+        let mut open_jtalk = open_jtalk();
+        let texts = ["こんにちは", "", "さようなら", "こんにちは"];
+        let expected = texts
+            .iter()
+            .map(|text| open_jtalk.extract_fullcontext(text).ok())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            expected,
+            open_jtalk
+                .extract_fullcontext_batch(&texts)
+                .into_iter()
+                .map(Result::ok)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[rstest]
+    fn open_jtalk_pipeline_works() {
+        use std::sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        };
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let pipeline = Pipeline::builder()
+            .disable(Stage::SetUnvoicedVowel)
+            .hook_after(Stage::SetDigit, {
+                let calls = calls.clone();
+                move |njd| {
+                    assert!(!njd.nodes()?.is_empty());
+                    calls.fetch_add(1, Ordering::Relaxed);
+                    Ok(())
+                }
+            })
+            .build()
+            .unwrap();
+        let mut open_jtalk = open_jtalk().with_pipeline(pipeline);
+        assert!(!open_jtalk
+            .extract_fullcontext("こんにちは")
+            .unwrap()
+            .is_empty());
+        assert_eq!(1, calls.load(Ordering::Relaxed));
+
+        open_jtalk.set_pipeline(Pipeline::builder().labels(false).build().unwrap());
+        assert_eq!(
+            Vec::<String>::new(),
+            open_jtalk.extract_fullcontext("こんにちは").unwrap()
+        );
+    }
+
+    #[rstest]
+    fn open_jtalk_analyze_without_text2mecab_works() {
+        let mut open_jtalk =
+            open_jtalk().with_pipeline(Pipeline::builder().text2mecab(false).build().unwrap());
+        let text = "こんにちは";
+        let analysis = open_jtalk.analyze(text).unwrap();
+        for node in &analysis.nodes {
+            assert_eq!(node.node.string, text[node.span.range.clone()]);
+        }
+    }
+
+    #[rstest]
+    fn open_jtalk_extract_fullcontext_with_overrides_works() {
+        let mut open_jtalk = open_jtalk();
+        let labels = open_jtalk
+            .extract_fullcontext_with_overrides(
+                "明日は晴れ",
+                &[ReadingOverride::new(0..6, "ミョーニチ", 1)],
+            )
+            .unwrap();
+        let phonemes = labels
+            .iter()
+            .map(|label| label.parse::<FullContextLabel>().unwrap())
+            .filter_map(|label| label.phoneme().map(ToOwned::to_owned))
+            .collect::<Vec<_>>();
+        assert_eq!(["sil", "my", "o", "o", "n", "i"], phonemes[..6]);
+
+        assert!(matches!(
+            open_jtalk.extract_fullcontext_with_overrides(
+                "明日は晴れ",
+                &[ReadingOverride::new(100..103, "ア", 0)],
+            ),
+            Err(Error::UnmatchedOverride { .. })
+        ));
+        assert!(!open_jtalk.extract_fullcontext("明日").unwrap().is_empty());
+    }
+
+    #[rstest]
+    fn open_jtalk_extract_prosody_works() {
+        let symbols = open_jtalk().extract_prosody("こんにちは", true).unwrap();
+        assert_eq!(
+            "^ k o [ N n i ch i w a $",
+            symbols
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(" ")
+        );
+    }
+
+    #[rstest]
+    fn open_jtalk_analyze_works() {
+        let mut open_jtalk = open_jtalk();
+        let text = "こんにちは";
+        let analysis = open_jtalk.analyze(text).unwrap();
+        assert_eq!(
+            open_jtalk.extract_fullcontext(text).unwrap(),
+            analysis.labels
+        );
+        assert!(!analysis.nodes.is_empty());
+        assert!(!analysis.accent_phrases.is_empty());
+        assert_eq!(
+            text,
+            analysis
+                .morphemes
+                .iter()
+                .map(|m| &*m.string)
+                .collect::<String>()
+        );
+        for node in &analysis.nodes {
+            assert_eq!(node.node.string, text[node.span.range.clone()]);
+        }
+    }
+
+    #[rstest]
+    fn open_jtalk_extract_fullcontext_by_sentence_works() {
+        let mut open_jtalk = open_jtalk();
+        let text = "こんにちは。\nさようなら";
+        let sentences = open_jtalk
+            .extract_fullcontext_by_sentence(text, &SentenceSplitter::default())
+            .unwrap();
+        assert_eq!(
+            vec![(0..18, 0..6), (19..34, 7..12)],
+            sentences
+                .iter()
+                .map(|s| (s.range.clone(), s.char_range.clone()))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            open_jtalk.extract_fullcontext("こんにちは。").unwrap(),
+            sentences[0].labels
+        );
+    }
+}
@@ -0,0 +1,529 @@
+use super::*;
+use std::{fmt, sync::Arc};
+
+/// text2mecabからラベル生成までの処理の段階。
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Stage {
+    Text2Mecab,
+    Analysis,
+    Mecab2Njd,
+    SetPronunciation,
+    SetDigit,
+    SetAccentPhrase,
+    SetAccentType,
+    SetUnvoicedVowel,
+    SetLongVowel,
+    Njd2JpCommon,
+    MakeLabel,
+}
+
+/// NJDを書き換える段階の既定の順序。
+pub const NJD_STAGES: [Stage; 6] = [
+    Stage::SetPronunciation,
+    Stage::SetDigit,
+    Stage::SetAccentPhrase,
+    Stage::SetAccentType,
+    Stage::SetUnvoicedVowel,
+    Stage::SetLongVowel,
+];
+
+/// NJDの段階の間で守るべき順序(前者が後者より先)。各段階が読み書きするノードのフィールドから決めている。
+///
+/// `set_long_vowel`は`pron`の長音を書き換えるだけなので、アクセントや無声化の段階とは順序を問わない。
+const NJD_STAGE_DEPENDENCIES: [(Stage, Stage); 12] = [
+    // 以降の段階はすべて`pron`と`mora_size`を使う
+    (Stage::SetPronunciation, Stage::SetDigit),
+    (Stage::SetPronunciation, Stage::SetAccentPhrase),
+    (Stage::SetPronunciation, Stage::SetAccentType),
+    (Stage::SetPronunciation, Stage::SetUnvoicedVowel),
+    (Stage::SetPronunciation, Stage::SetLongVowel),
+    // 数字のノードをまとめ、その読みと`acc`、`chain_flag`を決める
+    (Stage::SetDigit, Stage::SetAccentPhrase),
+    (Stage::SetDigit, Stage::SetAccentType),
+    (Stage::SetDigit, Stage::SetUnvoicedVowel),
+    (Stage::SetDigit, Stage::SetLongVowel),
+    // `chain_flag`からアクセント句を決める
+    (Stage::SetAccentPhrase, Stage::SetAccentType),
+    (Stage::SetAccentPhrase, Stage::SetUnvoicedVowel),
+    // アクセント核のモーラは無声化しない
+    (Stage::SetAccentType, Stage::SetUnvoicedVowel),
+];
+
+/// 段階の後に呼ばれるコールバック。
+pub type NjdHook = Arc<dyn Fn(&mut Njd) -> Result<()> + Send + Sync>;
+
+impl Stage {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Text2Mecab => "text2mecab",
+            Self::Analysis => "analysis",
+            Self::Mecab2Njd => "mecab2njd",
+            Self::SetPronunciation => "set_pronunciation",
+            Self::SetDigit => "set_digit",
+            Self::SetAccentPhrase => "set_accent_phrase",
+            Self::SetAccentType => "set_accent_type",
+            Self::SetUnvoicedVowel => "set_unvoiced_vowel",
+            Self::SetLongVowel => "set_long_vowel",
+            Self::Njd2JpCommon => "njd2jpcommon",
+            Self::MakeLabel => "make_label",
+        }
+    }
+
+    pub fn is_njd_stage(self) -> bool {
+        NJD_STAGES.contains(&self)
+    }
+
+    pub(crate) fn run_njd(self, njd: &mut Njd) -> Result<()> {
+        match self {
+            Self::SetPronunciation => njd.set_pronunciation(),
+            Self::SetDigit => njd.set_digit(),
+            Self::SetAccentPhrase => njd.set_accent_phrase(),
+            Self::SetAccentType => njd.set_accent_type(),
+            Self::SetUnvoicedVowel => njd.set_unvoiced_vowel(),
+            Self::SetLongVowel => njd.set_long_vowel(),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl std::str::FromStr for Stage {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        [
+            Self::Text2Mecab,
+            Self::Analysis,
+            Self::Mecab2Njd,
+            Self::Njd2JpCommon,
+            Self::MakeLabel,
+        ]
+        .into_iter()
+        .chain(NJD_STAGES)
+        .find(|stage| stage.name() == s)
+        .ok_or_else(|| Error::Parse {
+            what: "stage",
+            input: s.to_owned(),
+        })
+    }
+}
+
+/// [`OpenJtalk`]が行う処理の構成。
+///
+/// NJDを書き換える段階([`NJD_STAGES`])は無効にしたり並べ替えたりでき、
+/// `mecab2njd`とNJDの各段階の後にはNJDを受け取るコールバックを挟める。
+#[derive(Clone)]
+pub struct Pipeline {
+    text2mecab: bool,
+    labels: bool,
+    /// `mecab2njd`の後に呼ばれるコールバック。
+    after_mecab2njd: Vec<NjdHook>,
+    njd_stages: Vec<PipelineStage>,
+}
+
+#[derive(Clone)]
+struct PipelineStage {
+    stage: Stage,
+    enabled: bool,
+    hooks: Vec<NjdHook>,
+}
+
+#[derive(Clone)]
+pub struct PipelineBuilder {
+    text2mecab: bool,
+    labels: bool,
+    order: Vec<Stage>,
+    disabled: Vec<Stage>,
+    hooks: Vec<(Stage, NjdHook)>,
+}
+
+impl Pipeline {
+    pub fn builder() -> PipelineBuilder {
+        PipelineBuilder::default()
+    }
+
+    pub fn is_enabled(&self, stage: Stage) -> bool {
+        match stage {
+            Stage::Text2Mecab => self.text2mecab,
+            Stage::Analysis | Stage::Mecab2Njd => true,
+            Stage::Njd2JpCommon | Stage::MakeLabel => self.labels,
+            _ => self
+                .njd_stages
+                .iter()
+                .any(|s| s.stage == stage && s.enabled),
+        }
+    }
+
+    /// 有効な段階を実行順に返す。
+    pub fn stages(&self) -> Vec<Stage> {
+        let mut stages = vec![];
+        if self.text2mecab {
+            stages.push(Stage::Text2Mecab);
+        }
+        stages.extend([Stage::Analysis, Stage::Mecab2Njd]);
+        stages.extend(
+            self.njd_stages
+                .iter()
+                .filter(|s| s.enabled)
+                .map(|s| s.stage),
+        );
+        if self.labels {
+            stages.extend([Stage::Njd2JpCommon, Stage::MakeLabel]);
+        }
+        stages
+    }
+
+    /// 現在の構成を元にした[`PipelineBuilder`]を返す。
+    pub fn to_builder(&self) -> PipelineBuilder {
+        let mut hooks = self
+            .after_mecab2njd
+            .iter()
+            .map(|hook| (Stage::Mecab2Njd, hook.clone()))
+            .collect::<Vec<_>>();
+        for s in &self.njd_stages {
+            hooks.extend(s.hooks.iter().map(|hook| (s.stage, hook.clone())));
+        }
+        PipelineBuilder {
+            text2mecab: self.text2mecab,
+            labels: self.labels,
+            order: self.njd_stages.iter().map(|s| s.stage).collect(),
+            disabled: self
+                .njd_stages
+                .iter()
+                .filter(|s| !s.enabled)
+                .map(|s| s.stage)
+                .collect(),
+            hooks,
+        }
+    }
+
+    /// `stage`の直前に`hook`を呼ぶようにしたものを返す。`stage`はNJDの段階でなければならない。
+    pub(crate) fn with_hook_before(&self, stage: Stage, hook: NjdHook) -> Self {
+        let mut pipeline = self.clone();
+        match pipeline.njd_stages.iter().position(|s| s.stage == stage) {
+            Some(0) | None => pipeline.after_mecab2njd.push(hook),
+            Some(i) => pipeline.njd_stages[i - 1].hooks.push(hook),
+        }
+        pipeline
+    }
+
+    pub(crate) fn uses_text2mecab(&self) -> bool {
+        self.text2mecab
+    }
+
+    pub(crate) fn makes_labels(&self) -> bool {
+        self.labels
+    }
+
+    /// `mecab2njd`の後からラベル生成の前までを行う。各段階の前に`check`を呼ぶ。
+    pub(crate) fn run_njd(&self, njd: &mut Njd, check: impl Fn() -> Result<()>) -> Result<()> {
+        for hook in &self.after_mecab2njd {
+            hook(njd)?;
+        }
+        for s in &self.njd_stages {
+            check()?;
+            if s.enabled {
+                s.stage.run_njd(njd)?;
+            }
+            for hook in &s.hooks {
+                hook(njd)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for Pipeline {
+    fn default() -> Self {
+        Self {
+            text2mecab: true,
+            labels: true,
+            after_mecab2njd: vec![],
+            njd_stages: NJD_STAGES
+                .iter()
+                .map(|&stage| PipelineStage {
+                    stage,
+                    enabled: true,
+                    hooks: vec![],
+                })
+                .collect(),
+        }
+    }
+}
+
+impl fmt::Debug for Pipeline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Pipeline")
+            .field("stages", &self.stages())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for PipelineBuilder {
+    fn default() -> Self {
+        Self {
+            text2mecab: true,
+            labels: true,
+            order: NJD_STAGES.to_vec(),
+            disabled: vec![],
+            hooks: vec![],
+        }
+    }
+}
+
+impl PipelineBuilder {
+    /// `text2mecab`で入力を正規化するかどうか。無効にすると入力をそのままMeCabに渡す。
+    pub fn text2mecab(mut self, enabled: bool) -> Self {
+        self.text2mecab = enabled;
+        self
+    }
+
+    /// `njd2jpcommon`と`make_label`を行うかどうか。無効にするとラベルは空になる。
+    pub fn labels(mut self, enabled: bool) -> Self {
+        self.labels = enabled;
+        self
+    }
+
+    pub fn enable(mut self, stage: Stage) -> Self {
+        self.set_enabled(stage, true);
+        self
+    }
+
+    pub fn disable(mut self, stage: Stage) -> Self {
+        self.set_enabled(stage, false);
+        self
+    }
+
+    pub fn set_enabled(&mut self, stage: Stage, enabled: bool) {
+        match stage {
+            Stage::Text2Mecab => self.text2mecab = enabled,
+            Stage::Njd2JpCommon | Stage::MakeLabel => self.labels = enabled,
+            _ => {
+                self.disabled.retain(|&s| s != stage);
+                if !enabled {
+                    self.disabled.push(stage);
+                }
+            }
+        }
+    }
+
+    /// NJDの段階の実行順を指定する。[`NJD_STAGES`]を並べ替えたものでなければならない。
+    pub fn njd_order(mut self, order: impl IntoIterator<Item = Stage>) -> Self {
+        self.order = order.into_iter().collect();
+        self
+    }
+
+    /// `stage`の後に`hook`を呼ぶ。`stage`は`Mecab2Njd`かNJDの段階でなければならない。
+    ///
+    /// `stage`が無効になっていても、その位置で呼ばれる。
+    pub fn hook_after(
+        mut self,
+        stage: Stage,
+        hook: impl Fn(&mut Njd) -> Result<()> + Send + Sync + 'static,
+    ) -> Self {
+        self.hooks.push((stage, Arc::new(hook)));
+        self
+    }
+
+    /// `stage`を無効にし、代わりにその位置で`hook`を呼ぶ。
+    pub fn replace(
+        self,
+        stage: Stage,
+        hook: impl Fn(&mut Njd) -> Result<()> + Send + Sync + 'static,
+    ) -> Self {
+        self.disable(stage).hook_after(stage, hook)
+    }
+
+    pub fn build(self) -> Result<Pipeline> {
+        let invalid = |reason: String| Error::InvalidPipeline { reason };
+
+        let mut sorted = self.order.clone();
+        sorted.sort_by_key(|stage| NJD_STAGES.iter().position(|s| s == stage));
+        sorted.dedup();
+        if sorted.len() != self.order.len() || sorted != NJD_STAGES {
+            return Err(invalid(format!(
+                "NJD stages must be a permutation of {NJD_STAGES:?}, got {:?}",
+                self.order
+            )));
+        }
+        for stage in &self.disabled {
+            if !stage.is_njd_stage() {
+                return Err(invalid(format!("`{stage}` cannot be disabled")));
+            }
+        }
+
+        let enabled = |stage: &Stage| !self.disabled.contains(stage);
+        let position = |stage: Stage| self.order.iter().position(|&s| s == stage);
+        for (before, after) in NJD_STAGE_DEPENDENCIES {
+            if enabled(&before) && enabled(&after) && position(before) > position(after) {
+                return Err(invalid(format!("`{before}` must run before `{after}`")));
+            }
+        }
+
+        let mut after_mecab2njd = vec![];
+        let mut njd_stages = self
+            .order
+            .iter()
+            .map(|&stage| PipelineStage {
+                stage,
+                enabled: enabled(&stage),
+                hooks: vec![],
+            })
+            .collect::<Vec<_>>();
+        for (stage, hook) in self.hooks {
+            if stage == Stage::Mecab2Njd {
+                after_mecab2njd.push(hook);
+            } else if let Some(s) = njd_stages.iter_mut().find(|s| s.stage == stage) {
+                s.hooks.push(hook);
+            } else {
+                return Err(invalid(format!("cannot hook after `{stage}`")));
+            }
+        }
+
+        Ok(Pipeline {
+            text2mecab: self.text2mecab,
+            labels: self.labels,
+            after_mecab2njd,
+            njd_stages,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[rstest]
+    fn default_pipeline_runs_all_stages() {
+        assert_eq!(
+            vec![
+                Stage::Text2Mecab,
+                Stage::Analysis,
+                Stage::Mecab2Njd,
+                Stage::SetPronunciation,
+                Stage::SetDigit,
+                Stage::SetAccentPhrase,
+                Stage::SetAccentType,
+                Stage::SetUnvoicedVowel,
+                Stage::SetLongVowel,
+                Stage::Njd2JpCommon,
+                Stage::MakeLabel,
+            ],
+            Pipeline::default().stages()
+        );
+    }
+
+    #[rstest]
+    fn pipeline_builder_works() {
+        let pipeline = Pipeline::builder()
+            .disable(Stage::SetUnvoicedVowel)
+            .njd_order([
+                Stage::SetPronunciation,
+                Stage::SetDigit,
+                Stage::SetLongVowel,
+                Stage::SetAccentPhrase,
+                Stage::SetAccentType,
+                Stage::SetUnvoicedVowel,
+            ])
+            .hook_after(Stage::SetLongVowel, |_| Ok(()))
+            .build()
+            .unwrap();
+        assert!(!pipeline.is_enabled(Stage::SetUnvoicedVowel));
+        assert_eq!(
+            &[
+                Stage::SetPronunciation,
+                Stage::SetDigit,
+                Stage::SetLongVowel,
+                Stage::SetAccentPhrase,
+                Stage::SetAccentType,
+            ],
+            &pipeline.stages()[3..8]
+        );
+        let rebuilt = pipeline.to_builder().build().unwrap();
+        assert_eq!(pipeline.stages(), rebuilt.stages());
+        assert_eq!(1, rebuilt.njd_stages[2].hooks.len());
+    }
+
+    #[rstest]
+    fn pipeline_builder_replace_works() {
+        let pipeline = Pipeline::builder()
+            .replace(Stage::SetDigit, |_| Ok(()))
+            .build()
+            .unwrap();
+        assert!(!pipeline.is_enabled(Stage::SetDigit));
+        assert_eq!(1, pipeline.njd_stages[1].hooks.len());
+    }
+
+    #[rstest]
+    #[case(Pipeline::builder().njd_order([Stage::SetPronunciation]))]
+    #[case(Pipeline::builder().njd_order([
+        Stage::SetDigit,
+        Stage::SetPronunciation,
+        Stage::SetAccentPhrase,
+        Stage::SetAccentType,
+        Stage::SetUnvoicedVowel,
+        Stage::SetLongVowel,
+    ]))]
+    #[case(Pipeline::builder().njd_order([
+        Stage::SetPronunciation,
+        Stage::SetDigit,
+        Stage::SetAccentType,
+        Stage::SetAccentPhrase,
+        Stage::SetUnvoicedVowel,
+        Stage::SetLongVowel,
+    ]))]
+    #[case(Pipeline::builder().njd_order([
+        Stage::SetPronunciation,
+        Stage::SetAccentPhrase,
+        Stage::SetDigit,
+        Stage::SetAccentType,
+        Stage::SetUnvoicedVowel,
+        Stage::SetLongVowel,
+    ]))]
+    #[case(Pipeline::builder().njd_order([
+        Stage::SetPronunciation,
+        Stage::SetDigit,
+        Stage::SetAccentPhrase,
+        Stage::SetUnvoicedVowel,
+        Stage::SetAccentType,
+        Stage::SetLongVowel,
+    ]))]
+    #[case(Pipeline::builder().disable(Stage::Analysis))]
+    #[case(Pipeline::builder().hook_after(Stage::MakeLabel, |_| Ok(())))]
+    fn pipeline_builder_rejects_invalid_pipelines(#[case] builder: PipelineBuilder) {
+        assert!(matches!(
+            builder.build(),
+            Err(Error::InvalidPipeline { .. })
+        ));
+    }
+
+    #[rstest]
+    fn pipeline_builder_allows_reordering_disabled_stages() {
+        Pipeline::builder()
+            .disable(Stage::SetPronunciation)
+            .njd_order([
+                Stage::SetDigit,
+                Stage::SetPronunciation,
+                Stage::SetAccentPhrase,
+                Stage::SetAccentType,
+                Stage::SetUnvoicedVowel,
+                Stage::SetLongVowel,
+            ])
+            .build()
+            .unwrap();
+    }
+
+    #[rstest]
+    fn stage_from_str_works() {
+        for stage in NJD_STAGES {
+            assert_eq!(stage, stage.name().parse().unwrap());
+        }
+        assert!("unknown".parse::<Stage>().is_err());
+    }
+}
@@ -0,0 +1,224 @@
+use super::*;
+use camino::Utf8Path;
+use std::{
+    mem::ManuallyDrop,
+    ops::{Deref, DerefMut},
+    sync::{Condvar, Mutex, MutexGuard, PoisonError},
+};
+
+/// 辞書を共有する複数の[`OpenJtalk`]を保持し、呼び出しごとに1つを貸し出す。
+///
+/// `Sync`なので、`Arc<OpenJtalkPool>`としてスレッド間で共有できる。
+/// 空きがないときは、他の呼び出しが返却するまで待つ。
+pub struct OpenJtalkPool {
+    model: MecabModel,
+    idle: Mutex<Vec<OpenJtalk>>,
+    returned: Condvar,
+    size: usize,
+}
+
+/// [`OpenJtalkPool::get`]で借りた[`OpenJtalk`]。`Drop`でプールに返却される。
+pub struct PooledOpenJtalk<'a> {
+    pool: &'a OpenJtalkPool,
+    open_jtalk: ManuallyDrop<OpenJtalk>,
+}
+
+impl OpenJtalkPool {
+    pub fn new(dic_dir: impl AsRef<Utf8Path>, size: usize) -> Result<Self> {
+        Self::from_model(MecabModel::load(dic_dir)?, size)
+    }
+
+    pub fn new_with_userdic(
+        dic_dir: &Utf8Path,
+        userdic: Option<&Utf8Path>,
+        size: usize,
+    ) -> Result<Self> {
+        Self::from_model(MecabModel::load_with_userdic(dic_dir, userdic)?, size)
+    }
+
+    /// `size`個の`OpenJtalk`を作る。`size`が0の場合は1として扱う。
+    pub fn from_model(model: MecabModel, size: usize) -> Result<Self> {
+        Self::from_model_with_pipeline(model, size, &Pipeline::default())
+    }
+
+    /// [`OpenJtalkPool::from_model`]と同じだが、各`OpenJtalk`に`pipeline`を設定する。
+    pub fn from_model_with_pipeline(
+        model: MecabModel,
+        size: usize,
+        pipeline: &Pipeline,
+    ) -> Result<Self> {
+        let size = size.max(1);
+        let idle = (0..size)
+            .map(|_| Ok(OpenJtalk::from_model(&model)?.with_pipeline(pipeline.clone())))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            model,
+            idle: Mutex::new(idle),
+            returned: Condvar::new(),
+            size,
+        })
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn model(&self) -> &MecabModel {
+        &self.model
+    }
+
+    /// 空いている`OpenJtalk`を借りる。空きがなければ返却されるまで待つ。
+    pub fn get(&self) -> PooledOpenJtalk<'_> {
+        let mut idle = self.lock();
+        loop {
+            if let Some(open_jtalk) = idle.pop() {
+                return PooledOpenJtalk {
+                    pool: self,
+                    open_jtalk: ManuallyDrop::new(open_jtalk),
+                };
+            }
+            idle = self
+                .returned
+                .wait(idle)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    /// 空いている`OpenJtalk`があれば借りる。
+    pub fn try_get(&self) -> Option<PooledOpenJtalk<'_>> {
+        self.lock().pop().map(|open_jtalk| PooledOpenJtalk {
+            pool: self,
+            open_jtalk: ManuallyDrop::new(open_jtalk),
+        })
+    }
+
+    pub fn extract_fullcontext(&self, text: impl AsRef<str>) -> Result<Vec<String>> {
+        self.get().extract_fullcontext(text)
+    }
+
+    pub fn analyze(&self, text: &str) -> Result<Analysis> {
+        self.get().analyze(text)
+    }
+
+    pub fn extract_fullcontext_by_sentence(
+        &self,
+        text: &str,
+        splitter: &SentenceSplitter,
+    ) -> Result<Vec<SentenceFullcontext>> {
+        self.get().extract_fullcontext_by_sentence(text, splitter)
+    }
+
+    // `OpenJtalk`の出し入れの途中でパニックすることはないので、poisonは無視してよい。
+    fn lock(&self) -> MutexGuard<'_, Vec<OpenJtalk>> {
+        self.idle.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Deref for PooledOpenJtalk<'_> {
+    type Target = OpenJtalk;
+    fn deref(&self) -> &Self::Target {
+        &self.open_jtalk
+    }
+}
+
+impl DerefMut for PooledOpenJtalk<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.open_jtalk
+    }
+}
+
+impl Drop for PooledOpenJtalk<'_> {
+    fn drop(&mut self) {
+        // SAFETY: `open_jtalk`はこの後使われない。
+        let mut open_jtalk = unsafe { ManuallyDrop::take(&mut self.open_jtalk) };
+        // フックのパニックなどで解析が中断されると、その入力の状態が残ったままになる。
+        // 次の呼び出しに持ち越さないようにリセットし、できなければ作り直す。
+        if open_jtalk.refresh().is_err() {
+            if let Ok(fresh) = OpenJtalk::from_model(&self.pool.model) {
+                open_jtalk = fresh.with_pipeline(open_jtalk.pipeline().clone());
+            }
+        }
+        self.pool.lock().push(open_jtalk);
+        self.pool.returned.notify_one();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::{
+        panic::AssertUnwindSafe,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+    };
+
+    fn pool(size: usize) -> OpenJtalkPool {
+        OpenJtalkPool::new(
+            Utf8Path::new(std::env!("CARGO_MANIFEST_DIR")).join("src/mecab/testdata/mecab_load"),
+            size,
+        )
+        .unwrap()
+    }
+
+    #[rstest]
+    fn open_jtalk_pool_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<OpenJtalkPool>();
+        assert_send_sync::<MecabModel>();
+    }
+
+    #[rstest]
+    fn open_jtalk_pool_works_across_threads() {
+        let pool = Arc::new(pool(2));
+        let expected = pool.extract_fullcontext("こんにちは").unwrap();
+        let handles = (0..8)
+            .map(|_| {
+                let pool = pool.clone();
+                std::thread::spawn(move || pool.extract_fullcontext("こんにちは").unwrap())
+            })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            assert_eq!(expected, handle.join().unwrap());
+        }
+        assert_eq!(2, pool.lock().len());
+    }
+
+    #[rstest]
+    fn open_jtalk_pool_try_get_works() {
+        let pool = pool(1);
+        let guard = pool.try_get().unwrap();
+        assert!(pool.try_get().is_none());
+        drop(guard);
+        assert!(pool.try_get().is_some());
+    }
+
+    #[rstest]
+    fn open_jtalk_pool_resets_after_panic() {
+        let expected = pool(1).extract_fullcontext("こんにちは").unwrap();
+
+        let panics = Arc::new(AtomicBool::new(true));
+        let pipeline = Pipeline::builder()
+            .hook_after(Stage::Mecab2Njd, {
+                let panics = panics.clone();
+                move |_| {
+                    if panics.load(Ordering::SeqCst) {
+                        panic!("hook panicked");
+                    }
+                    Ok(())
+                }
+            })
+            .build()
+            .unwrap();
+        let pool =
+            OpenJtalkPool::from_model_with_pipeline(pool(1).model().clone(), 1, &pipeline).unwrap();
+        let result =
+            std::panic::catch_unwind(AssertUnwindSafe(|| pool.extract_fullcontext("さようなら")));
+        assert!(result.is_err());
+
+        panics.store(false, Ordering::SeqCst);
+        assert_eq!(expected, pool.extract_fullcontext("こんにちは").unwrap());
+    }
+}
@@ -0,0 +1,195 @@
+use super::*;
+use std::{fmt, str::FromStr};
+
+/// 韻律記号付きの音素列の要素。ESPnetの`pyopenjtalk_prosody`と同じ表記を使う。
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ProsodySymbol {
+    Phoneme(String),
+    /// 文頭(`^`)。
+    Start,
+    /// 文末(`$`)。
+    End,
+    /// 疑問形の文末(`?`)。
+    QuestionEnd,
+    /// ポーズ(`_`)。
+    Pause,
+    /// アクセント句の境界(`#`)。
+    AccentPhraseBoundary,
+    /// ピッチの上昇(`[`)。
+    Rise,
+    /// ピッチの下降(`]`)。アクセント核の直後に置かれる。
+    Fall,
+}
+
+impl ProsodySymbol {
+    /// フルコンテキストラベルのA・E・Fの素性から韻律記号を求める。
+    ///
+    /// `drop_unvoiced_vowels`が`true`の場合、無声化された母音(`A`など)を小文字にする。
+    pub fn from_labels(labels: &[FullContextLabel], drop_unvoiced_vowels: bool) -> Vec<Self> {
+        let mut symbols = vec![];
+        for (i, label) in labels.iter().enumerate() {
+            let phoneme = label.phoneme().unwrap_or_default();
+            match phoneme {
+                "sil" if i == 0 => symbols.push(Self::Start),
+                // 最後の`sil`のE3は、直前のアクセント句が疑問形かどうか。
+                "sil" => match label.e[2] {
+                    Some(0) => symbols.push(Self::End),
+                    Some(1) => symbols.push(Self::QuestionEnd),
+                    _ => {}
+                },
+                "pau" => symbols.push(Self::Pause),
+                _ => {
+                    symbols.push(Self::Phoneme(
+                        if drop_unvoiced_vowels && matches!(phoneme, "A" | "I" | "U" | "E" | "O") {
+                            phoneme.to_lowercase()
+                        } else {
+                            phoneme.to_owned()
+                        },
+                    ));
+                    let [a1, a2, a3] = label.a;
+                    let a2_next = labels.get(i + 1).and_then(|next| next.a[1]);
+                    if a3 == Some(1) && a2_next == Some(1) && ends_mora(phoneme) {
+                        symbols.push(Self::AccentPhraseBoundary);
+                    } else if matches!((a1, a2, a2_next), (Some(0), Some(a2), Some(next)) if next == a2 + 1)
+                        && a2 != label.f[0]
+                    {
+                        symbols.push(Self::Fall);
+                    } else if a2 == Some(1) && a2_next == Some(2) {
+                        symbols.push(Self::Rise);
+                    }
+                }
+            }
+        }
+        symbols
+    }
+
+    fn as_str(&self) -> &str {
+        match self {
+            Self::Phoneme(phoneme) => phoneme,
+            Self::Start => "^",
+            Self::End => "$",
+            Self::QuestionEnd => "?",
+            Self::Pause => "_",
+            Self::AccentPhraseBoundary => "#",
+            Self::Rise => "[",
+            Self::Fall => "]",
+        }
+    }
+}
+
+/// モーラの最後に来る音素(母音・撥音・促音)かどうか。
+fn ends_mora(phoneme: &str) -> bool {
+    matches!(
+        phoneme,
+        "a" | "i" | "u" | "e" | "o" | "A" | "I" | "U" | "E" | "O" | "N" | "cl"
+    )
+}
+
+impl fmt::Display for ProsodySymbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ProsodySymbol {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
+            "^" => Self::Start,
+            "$" => Self::End,
+            "?" => Self::QuestionEnd,
+            "_" => Self::Pause,
+            "#" => Self::AccentPhraseBoundary,
+            "[" => Self::Rise,
+            "]" => Self::Fall,
+            _ if !s.is_empty() && s.chars().all(|c| c.is_ascii_alphabetic()) => {
+                Self::Phoneme(s.to_owned())
+            }
+            _ => {
+                return Err(Error::Parse {
+                    what: "prosody symbol",
+                    input: s.to_owned(),
+                })
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn label(phoneme: &str, a: [i32; 3], e3: Option<i32>, f1: Option<i32>) -> FullContextLabel {
+        let mut label = FullContextLabel::default();
+        label.phonemes[2] = Some(phoneme.to_owned());
+        if phoneme != "sil" && phoneme != "pau" {
+            label.a = a.map(Some);
+        }
+        label.e[2] = e3;
+        label.f[0] = f1;
+        label
+    }
+
+    fn to_string(symbols: &[ProsodySymbol]) -> String {
+        symbols
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    #[rstest]
+    fn prosody_symbols_of_flat_phrase_works() {
+        // こんにちは(平板型、5モーラ)
+        let labels = [
+            label("sil", [0; 3], None, Some(5)),
+            label("k", [-4, 1, 5], None, Some(5)),
+            label("o", [-4, 1, 5], None, Some(5)),
+            label("N", [-3, 2, 4], None, Some(5)),
+            label("n", [-2, 3, 3], None, Some(5)),
+            label("i", [-2, 3, 3], None, Some(5)),
+            label("ch", [-1, 4, 2], None, Some(5)),
+            label("i", [-1, 4, 2], None, Some(5)),
+            label("w", [0, 5, 1], None, Some(5)),
+            label("a", [0, 5, 1], None, Some(5)),
+            label("sil", [0; 3], Some(0), None),
+        ];
+        assert_eq!(
+            "^ k o [ N n i ch i w a $",
+            to_string(&ProsodySymbol::from_labels(&labels, true))
+        );
+    }
+
+    #[rstest]
+    #[case(true, "^ a ] m e # u ?")]
+    #[case(false, "^ a ] m e # U ?")]
+    fn prosody_symbols_of_accented_phrases_work(
+        #[case] drop_unvoiced_vowels: bool,
+        #[case] expected: &str,
+    ) {
+        let labels = [
+            label("sil", [0; 3], None, Some(2)),
+            label("a", [0, 1, 2], None, Some(2)),
+            label("m", [1, 2, 1], None, Some(2)),
+            label("e", [1, 2, 1], None, Some(2)),
+            label("U", [0, 1, 1], None, Some(1)),
+            label("sil", [0; 3], Some(1), None),
+        ];
+        assert_eq!(
+            expected,
+            to_string(&ProsodySymbol::from_labels(&labels, drop_unvoiced_vowels))
+        );
+    }
+
+    #[rstest]
+    fn prosody_symbol_from_str_round_trips() {
+        for s in ["^", "$", "?", "_", "#", "[", "]", "ch", "U"] {
+            assert_eq!(s, s.parse::<ProsodySymbol>().unwrap().to_string());
+        }
+        assert!("".parse::<ProsodySymbol>().is_err());
+        assert!("a b".parse::<ProsodySymbol>().is_err());
+    }
+}
@@ -0,0 +1,173 @@
+use super::*;
+use std::ops::Range;
+
+/// [`OpenJtalk::extract_fullcontext_with_overrides`]で使う、読みとアクセントの上書き。
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReadingOverride {
+    /// 入力上のバイト範囲。
+    pub range: Range<usize>,
+    /// 発音(カタカナ)。
+    pub reading: String,
+    /// アクセント核の位置(1始まり)。0は平板型。
+    pub accent: usize,
+}
+
+impl ReadingOverride {
+    pub fn new(range: Range<usize>, reading: impl Into<String>, accent: usize) -> Self {
+        Self {
+            range,
+            reading: reading.into(),
+            accent,
+        }
+    }
+}
+
+/// `overrides`の範囲と重なるノードを1つにまとめ、読みとアクセントを置き換える。
+///
+/// 上書きの範囲どうしが重なっていればエラーになる。ノードが2つの上書きにまたがる場合は後の上書きに含める。
+/// どのノードとも重ならない上書きがあればエラーになる。
+pub(crate) fn override_nodes(
+    nodes: Vec<AlignedNode>,
+    overrides: &[ReadingOverride],
+) -> Result<Vec<NjdNode>> {
+    let mut overrides = overrides.iter().collect::<Vec<_>>();
+    overrides.sort_by_key(|o| (o.range.start, o.range.end));
+    if let Some(w) = overrides
+        .windows(2)
+        .find(|w| w[0].range.end > w[1].range.start)
+    {
+        return Err(Error::OverlappingOverride {
+            start: w[1].range.start,
+            end: w[1].range.end,
+        });
+    }
+    let unmatched = |o: &ReadingOverride| Error::UnmatchedOverride {
+        start: o.range.start,
+        end: o.range.end,
+    };
+
+    // 次に適用する上書き
+    let mut next = 0;
+    // `result`の最後のノードが`overrides[next - 1]`によるもので、後続のノードをまとめられるかどうか
+    let mut open = false;
+    let mut result = Vec::<NjdNode>::with_capacity(nodes.len());
+    for AlignedNode { mut node, span } in nodes {
+        let overlaps =
+            |o: &ReadingOverride| o.range.start < span.range.end && span.range.start < o.range.end;
+        if let Some(&o) = overrides.get(next) {
+            if o.range.end <= span.range.start {
+                return Err(unmatched(o));
+            }
+            if overlaps(o) {
+                node.read = o.reading.clone();
+                node.pron = o.reading.clone();
+                node.acc = o.accent as i32;
+                node.mora_size = split_moras(&o.reading).len() as i32;
+                result.push(node);
+                next += 1;
+                open = true;
+                continue;
+            }
+        }
+        match result.last_mut() {
+            Some(merged) if open && overlaps(overrides[next - 1]) => {
+                merged.string.push_str(&node.string);
+                merged.orig.push_str(&node.orig);
+            }
+            _ => {
+                result.push(node);
+                open = false;
+            }
+        }
+    }
+    if let Some(&o) = overrides.get(next) {
+        return Err(unmatched(o));
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn aligned(string: &str, pron: &str, range: Range<usize>) -> AlignedNode {
+        AlignedNode {
+            node: NjdNode {
+                string: string.to_owned(),
+                orig: string.to_owned(),
+                read: pron.to_owned(),
+                pron: pron.to_owned(),
+                acc: 1,
+                mora_size: split_moras(pron).len() as i32,
+                ..Default::default()
+            },
+            span: SourceSpan {
+                char_range: range.start / 3..range.end / 3,
+                range,
+            },
+        }
+    }
+
+    #[rstest]
+    fn override_nodes_works() {
+        let nodes = vec![
+            aligned("東", "ヒガシ", 0..3),
+            aligned("京", "キョー", 3..6),
+            aligned("へ", "エ", 6..9),
+        ];
+        let nodes = override_nodes(nodes, &[ReadingOverride::new(0..6, "トーキョー", 0)]).unwrap();
+        assert_eq!(
+            vec![("東京", "トーキョー", 0, 4), ("へ", "エ", 1, 1)],
+            nodes
+                .iter()
+                .map(|n| (&*n.string, &*n.pron, n.acc, n.mora_size))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!("東京", nodes[0].orig);
+    }
+
+    #[rstest]
+    fn override_nodes_fails_if_unmatched() {
+        let nodes = vec![aligned("へ", "エ", 0..3)];
+        assert!(matches!(
+            override_nodes(nodes, &[ReadingOverride::new(3..6, "ア", 0)]),
+            Err(Error::UnmatchedOverride { start: 3, end: 6 })
+        ));
+    }
+
+    #[rstest]
+    fn override_nodes_gives_a_straddling_node_to_the_later_override() {
+        let nodes = vec![
+            aligned("あ", "ア", 0..3),
+            aligned("い", "イ", 3..6),
+            aligned("う", "ウ", 6..9),
+        ];
+        let overrides = [
+            ReadingOverride::new(4..9, "ビー", 1),
+            ReadingOverride::new(0..4, "エー", 1),
+        ];
+        assert_eq!(
+            vec![("あ", "エー"), ("いう", "ビー")],
+            override_nodes(nodes, &overrides)
+                .unwrap()
+                .iter()
+                .map(|n| (&*n.string, &*n.pron))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[rstest]
+    fn override_nodes_rejects_overlapping_overrides() {
+        let nodes = vec![aligned("あ", "ア", 0..3), aligned("い", "イ", 3..6)];
+        let overrides = [
+            ReadingOverride::new(3..6, "ビー", 1),
+            ReadingOverride::new(0..4, "エー", 1),
+        ];
+        assert!(matches!(
+            override_nodes(nodes, &overrides),
+            Err(Error::OverlappingOverride { start: 3, end: 6 })
+        ));
+    }
+}
@@ -1,42 +1,84 @@
-use std::ops::{Deref, DerefMut};
+use std::{mem::MaybeUninit, ptr::NonNull};
 
 pub mod resources {
+    /// C側の初期化・解放関数を直接呼ぶための低レベルなインターフェイス。
+    ///
+    /// 通常は`Mecab::new`などのコンストラクタを使う。`clear`した後のリソースに対する操作は
+    /// [`Error::Uninitialized`](crate::Error::Uninitialized)を返す。
     #[allow(clippy::missing_safety_doc)]
-    pub unsafe trait Resource: Default {
+    pub unsafe trait Resource {
         unsafe fn initialize(&mut self) -> bool;
         unsafe fn clear(&mut self) -> bool;
     }
 }
 
-pub struct ManagedResource<R: resources::Resource>(R);
+/// C側の構造体をヒープ上に確保して保持する。
+///
+/// Rust側の値を移動しても構造体のアドレスは変わらないので、C側が構造体自身や
+/// そのフィールドを指すポインタを持っていても壊れない。
+/// 構造体の中身の解放(`*_clear`)は持ち主が行い、ここでは領域のみを解放する。
+pub(crate) struct RawBox<T> {
+    ptr: NonNull<MaybeUninit<T>>,
+    initialized: bool,
+}
 
-impl<R: resources::Resource> ManagedResource<R> {
-    pub fn initialize() -> Self {
-        let mut r: R = Default::default();
-        unsafe {
-            r.initialize();
+impl<T> RawBox<T> {
+    pub(crate) fn new() -> Self {
+        let ptr = Box::into_raw(Box::new(MaybeUninit::<T>::uninit()));
+        Self {
+            // SAFETY: `Box::into_raw`はnullを返さない。
+            ptr: unsafe { NonNull::new_unchecked(ptr) },
+            initialized: false,
         }
-        Self(r)
     }
-}
 
-impl<R: resources::Resource> Drop for ManagedResource<R> {
-    fn drop(&mut self) {
-        unsafe {
-            self.0.clear();
-        }
+    /// 初期化の有無に関わらず構造体へのポインタを返す。`*_initialize`に渡すためのもの。
+    pub(crate) fn as_uninit_ptr(&self) -> *mut T {
+        self.ptr.as_ptr().cast()
+    }
+
+    /// 初期化済みであれば構造体へのポインタを返す。
+    pub(crate) fn get(&self) -> Option<*mut T> {
+        self.initialized.then(|| self.as_uninit_ptr())
+    }
+
+    pub(crate) fn is_initialized(&self) -> bool {
+        self.initialized
+    }
+
+    pub(crate) fn set_initialized(&mut self, initialized: bool) {
+        self.initialized = initialized;
     }
 }
 
-impl<R: resources::Resource> DerefMut for ManagedResource<R> {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+// SAFETY: `Box<T>`と同じく、`T`を所有しているだけである。
+unsafe impl<T: Send> Send for RawBox<T> {}
+
+impl<T> Drop for RawBox<T> {
+    fn drop(&mut self) {
+        // SAFETY: `ptr`は`new`で`Box::into_raw`から得たもので、ここでのみ解放する。
+        drop(unsafe { Box::from_raw(self.ptr.as_ptr()) });
     }
 }
 
-impl<R: resources::Resource> Deref for ManagedResource<R> {
-    type Target = R;
-    fn deref(&self) -> &Self::Target {
-        &self.0
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    fn raw_box_address_is_stable_across_moves() {
+        let mut raw = RawBox::<[u64; 4]>::new();
+        assert!(raw.get().is_none());
+        unsafe { raw.as_uninit_ptr().write([1, 2, 3, 4]) };
+        raw.set_initialized(true);
+        let ptr = raw.get().unwrap();
+
+        let boxes = vec![raw];
+        let raw = std::thread::spawn(move || boxes.into_iter().next().unwrap())
+            .join()
+            .unwrap();
+        assert_eq!(Some(ptr), raw.get());
+        assert_eq!([1, 2, 3, 4], unsafe { *ptr });
     }
 }
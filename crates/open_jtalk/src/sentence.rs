@@ -0,0 +1,141 @@
+use std::ops::Range;
+
+/// 文(あるいは呼気段落)への分割規則。
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SentenceSplitter {
+    delimiters: Vec<char>,
+    split_on_newline: bool,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct SentenceSplitterBuilder {
+    inner: SentenceSplitter,
+}
+
+/// 分割された文。
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Sentence<'a> {
+    pub text: &'a str,
+    /// 元の文字列上のバイト範囲。
+    pub range: Range<usize>,
+    /// 元の文字列上の文字(`char`)単位の範囲。
+    pub char_range: Range<usize>,
+}
+
+impl Default for SentenceSplitter {
+    /// `。！？`と改行で区切る。
+    fn default() -> Self {
+        Self {
+            delimiters: vec!['。', '！', '？'],
+            split_on_newline: true,
+        }
+    }
+}
+
+impl SentenceSplitterBuilder {
+    /// 文末とみなす文字。区切り文字は直前の文に含まれ、連続する区切り文字はまとめて一つの文に含まれる。
+    pub fn delimiters(mut self, delimiters: impl IntoIterator<Item = char>) -> Self {
+        self.inner.delimiters = delimiters.into_iter().collect();
+        self
+    }
+
+    /// 改行で区切るかどうか。改行はどの文にも含まれない。
+    pub fn split_on_newline(mut self, split_on_newline: bool) -> Self {
+        self.inner.split_on_newline = split_on_newline;
+        self
+    }
+
+    pub fn build(self) -> SentenceSplitter {
+        self.inner
+    }
+}
+
+impl SentenceSplitter {
+    pub fn builder() -> SentenceSplitterBuilder {
+        SentenceSplitterBuilder::default()
+    }
+
+    /// `text`を文に分割する。
+    ///
+    /// 各文の前後の空白は取り除き、空になった文は返さない。
+    pub fn split<'a>(&self, text: &'a str) -> Vec<Sentence<'a>> {
+        let mut sentences = vec![];
+        let mut start = 0;
+        let mut chars = text.char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
+            let end = if self.split_on_newline && matches!(c, '\r' | '\n') {
+                i
+            } else if self.delimiters.contains(&c) {
+                while chars
+                    .next_if(|(_, c)| self.delimiters.contains(c))
+                    .is_some()
+                {}
+                chars.peek().map_or(text.len(), |&(i, _)| i)
+            } else {
+                continue;
+            };
+            sentences.extend(sentence(text, start..end));
+            start = chars.peek().map_or(text.len(), |&(i, _)| i);
+        }
+        sentences.extend(sentence(text, start..text.len()));
+        sentences
+    }
+}
+
+fn sentence(text: &str, range: Range<usize>) -> Option<Sentence<'_>> {
+    let untrimmed = &text[range.clone()];
+    let trimmed = untrimmed.trim_start();
+    let start = range.start + (untrimmed.len() - trimmed.len());
+    let trimmed = trimmed.trim_end();
+    if trimmed.is_empty() {
+        return None;
+    }
+    let char_start = text[..start].chars().count();
+    Some(Sentence {
+        text: trimmed,
+        range: start..start + trimmed.len(),
+        char_range: char_start..char_start + trimmed.chars().count(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::*;
+    use pretty_assertions::assert_eq;
+
+    #[rstest]
+    #[case(
+        SentenceSplitter::default(),
+        "こんにちは。元気？！\n  はい ",
+        &[("こんにちは。", 0..18, 0..6), ("元気？！", 18..30, 6..10), ("はい", 33..39, 13..15)]
+    )]
+    #[case(
+        SentenceSplitter::builder().delimiters(['、']).split_on_newline(false).build(),
+        "はい、そうです。\nいいえ",
+        &[("はい、", 0..9, 0..3), ("そうです。\nいいえ", 9..34, 3..12)]
+    )]
+    #[case(SentenceSplitter::default(), "", &[])]
+    #[case(SentenceSplitter::default(), "。\n\n", &[("。", 0..3, 0..1)])]
+    fn sentence_splitter_works(
+        #[case] splitter: SentenceSplitter,
+        #[case] text: &str,
+        #[case] expected: &[(&str, Range<usize>, Range<usize>)],
+    ) {
+        let sentences = splitter.split(text);
+        assert_eq!(
+            expected
+                .iter()
+                .map(|(text, range, char_range)| Sentence {
+                    text,
+                    range: range.clone(),
+                    char_range: char_range.clone(),
+                })
+                .collect::<Vec<_>>(),
+            sentences
+        );
+        for sentence in sentences {
+            assert_eq!(sentence.text, &text[sentence.range]);
+        }
+    }
+}
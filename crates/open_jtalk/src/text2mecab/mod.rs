@@ -0,0 +1,154 @@
+mod normalizer;
+mod rule;
+
+pub use normalizer::*;
+
+use std::{
+    ffi::{CStr, CString},
+    os::raw::c_char,
+};
+
+#[derive(Clone, PartialEq, Eq, Debug, thiserror::Error)]
+pub enum Text2MecabError {
+    #[error("text2mecab range error at byte {position}")]
+    Range { position: usize },
+    #[error("text2mecab invalid argument at byte {position}")]
+    InvalidArgument { position: usize },
+    #[error("input contained a NUL byte at byte {position}")]
+    Nul { position: usize },
+    #[error("text2mecab output was not valid UTF-8 at input byte {position}")]
+    InvalidUtf8 { position: usize },
+}
+
+impl Text2MecabError {
+    /// 変換に失敗した入力上のバイトオフセット。
+    pub fn position(&self) -> usize {
+        match *self {
+            Self::Range { position }
+            | Self::InvalidArgument { position }
+            | Self::Nul { position }
+            | Self::InvalidUtf8 { position } => position,
+        }
+    }
+}
+
+pub fn text2mecab(input: impl AsRef<str>) -> Result<String, Text2MecabError> {
+    let input = input.as_ref();
+    let text = CString::new(input).map_err(|e| Text2MecabError::Nul {
+        position: e.nul_position(),
+    })?;
+
+    let error = match text2mecab_growing(&text, input.len()) {
+        Ok(output) => match String::from_utf8(output) {
+            Ok(output) => return Ok(output),
+            Err(_) => Text2MecabError::InvalidUtf8 {
+                position: failure_position(&text),
+            },
+        },
+        Err(result) => {
+            let position = failure_position(&text);
+            if result == open_jtalk_sys::text2mecab_result_t::TEXT2MECAB_RESULT_RANGE_ERROR {
+                Text2MecabError::Range { position }
+            } else {
+                Text2MecabError::InvalidArgument { position }
+            }
+        }
+    };
+    Err(error)
+}
+
+/// 出力バッファを広げながら`text2mecab`を呼ぶ。
+///
+/// text2mecabは1バイトの半角文字を3バイトの全角文字に変換するのが最大の膨張のため、
+/// 入力長の3倍から始めて、それでも足りなければ倍々に広げて再試行する。
+fn text2mecab_growing(
+    text: &CStr,
+    input_len: usize,
+) -> Result<Vec<u8>, open_jtalk_sys::text2mecab_result_t> {
+    const EXPANSION_RATIO: usize = 3;
+    const MAX_RETRIES: usize = 4;
+
+    let mut size_of_output = input_len * EXPANSION_RATIO + 1;
+    let mut result = open_jtalk_sys::text2mecab_result_t::TEXT2MECAB_RESULT_RANGE_ERROR;
+    for _ in 0..MAX_RETRIES {
+        let mut output = Vec::<u8>::with_capacity(size_of_output);
+        result = unsafe {
+            open_jtalk_sys::text2mecab(
+                output.as_mut_ptr() as *mut c_char,
+                size_of_output,
+                text.as_ptr(),
+            )
+        };
+        match result {
+            open_jtalk_sys::text2mecab_result_t::TEXT2MECAB_RESULT_SUCCESS => {
+                unsafe {
+                    output.set_len(
+                        CStr::from_ptr(output.as_ptr() as *const c_char)
+                            .to_bytes()
+                            .len(),
+                    )
+                }
+                return Ok(output);
+            }
+            open_jtalk_sys::text2mecab_result_t::TEXT2MECAB_RESULT_RANGE_ERROR => {
+                size_of_output *= 2;
+            }
+            open_jtalk_sys::text2mecab_result_t::TEXT2MECAB_RESULT_INVALID_ARGUMENT => break,
+        }
+    }
+    Err(result)
+}
+
+/// 変換に成功する最長の接頭辞を二分探索し、失敗し始める位置を返す。
+///
+/// 出力がUTF-8として正しくない場合も失敗とみなす。
+/// text2mecabは先頭から順に変換するため、ある接頭辞で失敗するならそれより長い接頭辞でも失敗する。
+fn failure_position(text: &CStr) -> usize {
+    let bytes = text.to_bytes();
+    // UTF-8の継続バイト以外の位置が文字の境界になる
+    let boundaries = (0..bytes.len())
+        .filter(|&i| bytes[i] & 0xc0 != 0x80)
+        .chain([bytes.len()])
+        .collect::<Vec<_>>();
+    let converts = |end: usize| {
+        // SAFETY: `CStr`の接頭辞なので、NULを含まない。
+        let prefix = unsafe { CString::from_vec_unchecked(bytes[..end].to_vec()) };
+        text2mecab_growing(&prefix, end).is_ok_and(|output| std::str::from_utf8(&output).is_ok())
+    };
+
+    let (mut ok, mut ng) = (0, boundaries.len() - 1);
+    while ng - ok > 1 {
+        let mid = (ok + ng) / 2;
+        if converts(boundaries[mid]) {
+            ok = mid;
+        } else {
+            ng = mid;
+        }
+    }
+    boundaries[ok]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::*;
+    #[rstest]
+    #[case("", Ok("".into()))]
+    #[case("あいうえお", Ok("あいうえお".into()))]
+    #[case("あいう\nえお", Ok("あいうえお".into()))]
+    #[case("あい\0うえお", Err(Text2MecabError::Nul { position: 6 }))]
+    fn text2mecab_works(
+        #[case] input: impl AsRef<str>,
+        #[case] expected: Result<String, Text2MecabError>,
+    ) {
+        let result = text2mecab(input);
+        assert_eq!(expected, result);
+    }
+
+    #[rstest]
+    #[case("あ".repeat(10000), "あ".repeat(10000))]
+    #[case("a".repeat(10000), "ａ".repeat(10000))]
+    fn text2mecab_works_beyond_8192_bytes(#[case] input: String, #[case] expected: String) {
+        assert_eq!(Ok(expected), text2mecab(input));
+    }
+}
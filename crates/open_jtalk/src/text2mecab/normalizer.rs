@@ -0,0 +1,236 @@
+use super::rule;
+use std::ops::Range;
+
+const PAUSE: &str = "、";
+
+/// 改行の扱い。
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum NewlineHandling {
+    /// 改行を取り除く。C版の`text2mecab`と同じ挙動。
+    #[default]
+    Remove,
+    /// 改行を読点(`、`)に置き換え、ポーズとして読ませる。連続する改行は一つにまとめる。
+    Pause,
+}
+
+/// 絵文字などの、辞書で扱えない文字の扱い。
+///
+/// 対象となるのは絵文字・絵記号のブロック(U+1F000..=U+1FAFF、U+2600..=U+27BF、U+2B00..=U+2BFF)と、
+/// 絵文字の合成に使われる異体字セレクタ・ZWJ・キーキャップ・タグ文字。連続するものは一つの塊として扱う。
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub enum UnsupportedCharHandling {
+    /// そのまま残す。C版の`text2mecab`と同じ挙動。
+    #[default]
+    Keep,
+    /// 取り除く。
+    Drop,
+    /// 指定した文字列に置き換える。
+    Replace(String),
+}
+
+/// `text2mecab`のRust実装。
+///
+/// [`Text2Mecab::default`]はC版の[`text2mecab`](super::text2mecab)とバイト単位で同じ結果を返す。
+/// [`Text2Mecab::builder`]で正規化の規則を変えられる。
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Text2Mecab {
+    newline: NewlineHandling,
+    unsupported: UnsupportedCharHandling,
+    mappings: Vec<(String, String)>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Text2MecabBuilder {
+    inner: Text2Mecab,
+}
+
+impl Text2MecabBuilder {
+    pub fn newline(mut self, newline: NewlineHandling) -> Self {
+        self.inner.newline = newline;
+        self
+    }
+
+    pub fn unsupported(mut self, unsupported: UnsupportedCharHandling) -> Self {
+        self.inner.unsupported = unsupported;
+        self
+    }
+
+    /// 組み込みの変換表より優先される変換を追加する。
+    ///
+    /// 複数の変換が一致する場合は`from`が長いものが優先される。空の`from`は無視する。
+    pub fn mapping(mut self, from: impl Into<String>, to: impl Into<String>) -> Self {
+        self.inner.mappings.push((from.into(), to.into()));
+        self
+    }
+
+    pub fn mappings(
+        self,
+        mappings: impl IntoIterator<Item = (impl Into<String>, impl Into<String>)>,
+    ) -> Self {
+        mappings
+            .into_iter()
+            .fold(self, |this, (from, to)| this.mapping(from, to))
+    }
+
+    pub fn build(mut self) -> Text2Mecab {
+        self.inner.mappings.retain(|(from, _)| !from.is_empty());
+        self.inner
+            .mappings
+            .sort_by_key(|(from, _)| std::cmp::Reverse(from.len()));
+        self.inner
+    }
+}
+
+impl Text2Mecab {
+    pub fn builder() -> Text2MecabBuilder {
+        Text2MecabBuilder::default()
+    }
+
+    pub fn convert(&self, input: &str) -> String {
+        let mut output = String::with_capacity(input.len() * 3);
+        self.for_each_segment(input, |_, converted| output.push_str(converted));
+        output
+    }
+
+    /// 入力を先頭から変換し、入力上のバイト範囲とそれに対応する出力を順に`f`に渡す。
+    ///
+    /// 読み飛ばされた文字も空文字列として渡すため、範囲は隙間なく入力全体を覆う。
+    pub(crate) fn for_each_segment(&self, input: &str, mut f: impl FnMut(Range<usize>, &str)) {
+        let mut pos = 0;
+        while let Some(c) = input[pos..].chars().next() {
+            let rest = &input[pos..];
+            let (len, converted) = self.convert_head(c, rest);
+            f(pos..pos + len, converted);
+            pos += len;
+        }
+    }
+
+    /// `rest`の先頭を変換し、消費したバイト数と変換結果を返す。`c`は`rest`の先頭の文字。
+    fn convert_head<'a>(&'a self, c: char, rest: &'a str) -> (usize, &'a str) {
+        if let Some((from, to)) = self
+            .mappings
+            .iter()
+            .find(|(from, _)| rest.starts_with(from.as_str()))
+        {
+            return (from.len(), to);
+        }
+        if self.newline == NewlineHandling::Pause && matches!(c, '\r' | '\n') {
+            return (leading_len(rest, |c| matches!(c, '\r' | '\n')), PAUSE);
+        }
+        if c.is_ascii() || ('\u{ff61}'..='\u{ff9f}').contains(&c) {
+            if let Some((from, to)) = rule::CONV_LIST
+                .iter()
+                .find(|(from, _)| rest.starts_with(from))
+            {
+                return (from.len(), to);
+            }
+        }
+        if rule::is_control(c) {
+            return (c.len_utf8(), "");
+        }
+        if is_unsupported(c) {
+            match &self.unsupported {
+                UnsupportedCharHandling::Keep => {}
+                UnsupportedCharHandling::Drop => return (leading_len(rest, is_unsupported), ""),
+                UnsupportedCharHandling::Replace(placeholder) => {
+                    return (leading_len(rest, is_unsupported), placeholder)
+                }
+            }
+        }
+        (c.len_utf8(), &rest[..c.len_utf8()])
+    }
+}
+
+fn leading_len(s: &str, pred: impl Fn(char) -> bool) -> usize {
+    s.len() - s.trim_start_matches(pred).len()
+}
+
+fn is_unsupported(c: char) -> bool {
+    matches!(
+        c,
+        '\u{1f000}'..='\u{1faff}'
+            | '\u{2600}'..='\u{27bf}'
+            | '\u{2b00}'..='\u{2bff}'
+            | '\u{fe00}'..='\u{fe0f}'
+            | '\u{200d}'
+            | '\u{20e3}'
+            | '\u{e0000}'..='\u{e007f}'
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::*;
+    use pretty_assertions::assert_eq;
+
+    #[rstest]
+    #[case("")]
+    #[case("あいうえお")]
+    #[case("あいう\nえお")]
+    #[case("\tタブ\rと\r\n改行\u{7f}")]
+    #[case(" !\"#$%&'()*+,-./0123456789:;<=>?@")]
+    #[case("ABCDEFGHIJKLMNOPQRSTUVWXYZ[\\]^_`abcdefghijklmnopqrstuvwxyz{|}~")]
+    #[case("｡｢｣､･ｦｧｨｩｪｫｬｭｮｯｰｱｲｳｴｵｶｷｸｹｺｻｼｽｾｿﾀﾁﾂﾃﾄﾅﾆﾇﾈﾉﾊﾋﾌﾍﾎﾏﾐﾑﾒﾓﾔﾕﾖﾗﾘﾙﾚﾛﾜﾝﾞﾟ")]
+    #[case("ｳﾞｶﾞｷﾞｸﾞｹﾞｺﾞｻﾞｼﾞｽﾞｾﾞｿﾞﾀﾞﾁﾞﾂﾞﾃﾞﾄﾞﾊﾞﾋﾞﾌﾞﾍﾞﾎﾞﾊﾟﾋﾟﾌﾟﾍﾟﾎﾟ")]
+    #[case("ｱﾞﾝﾟ")]
+    #[case("今日は2022年7月1日です。VOICEVOX、ﾃｽﾄ！")]
+    #[case("絵文字😀👨\u{200d}👩\u{200d}👧と⭐☀\u{fe0f}と𠮷野家")]
+    fn text2mecab_default_is_compatible_with_c(#[case] input: &str) {
+        assert_eq!(
+            text2mecab(input).unwrap(),
+            Text2Mecab::default().convert(input)
+        );
+    }
+
+    #[rstest]
+    #[case(NewlineHandling::Remove, "あいうえおか")]
+    #[case(NewlineHandling::Pause, "あいう、えお、か")]
+    fn text2mecab_newline_works(#[case] newline: NewlineHandling, #[case] expected: &str) {
+        let text2mecab = Text2Mecab::builder().newline(newline).build();
+        assert_eq!(expected, text2mecab.convert("あいう\r\n\nえお\nか"));
+    }
+
+    #[rstest]
+    #[case(UnsupportedCharHandling::Keep, "あ😀👨\u{200d}👩い☀\u{fe0f}")]
+    #[case(UnsupportedCharHandling::Drop, "あい")]
+    #[case(UnsupportedCharHandling::Replace("〓".into()), "あ〓い〓")]
+    fn text2mecab_unsupported_works(
+        #[case] unsupported: UnsupportedCharHandling,
+        #[case] expected: &str,
+    ) {
+        let text2mecab = Text2Mecab::builder().unsupported(unsupported).build();
+        assert_eq!(expected, text2mecab.convert("あ😀👨\u{200d}👩い☀\u{fe0f}"));
+    }
+
+    #[rstest]
+    fn text2mecab_mapping_works() {
+        let text2mecab = Text2Mecab::builder()
+            .mapping("&", "アンド")
+            .mappings([("ﾃｽﾄ", "試験"), ("ﾃ", "て"), ("", "無視")])
+            .build();
+        assert_eq!("ＡアンドＢの試験てｓｔ", text2mecab.convert("A&Bのﾃｽﾄﾃst"));
+    }
+
+    #[rstest]
+    fn text2mecab_segments_cover_input() {
+        let input = "ab\nｶﾞ😀";
+        let text2mecab = Text2Mecab::builder()
+            .unsupported(UnsupportedCharHandling::Drop)
+            .build();
+        let mut segments = vec![];
+        text2mecab.for_each_segment(input, |range, converted| {
+            segments.push((range, converted.to_owned()))
+        });
+        assert_eq!(
+            vec![
+                (0..1, "ａ".to_owned()),
+                (1..2, "ｂ".to_owned()),
+                (2..3, "".to_owned()),
+                (3..9, "ガ".to_owned()),
+                (9..13, "".to_owned()),
+            ],
+            segments
+        );
+    }
+}
@@ -0,0 +1,196 @@
+//! text2mecabの変換表。
+//!
+//! OpenJTalkの`text2mecab_rule_utf_8.h`の`text2mecab_conv_list`と同じ内容・同じ順序で並べる。
+//! 先頭から順に照合して最初に一致したものが使われるため、濁点・半濁点付きの半角カナは単独の半角カナより前に置く。
+
+pub(crate) const CONV_LIST: &[(&str, &str)] = &[
+    (" ", "　"),
+    ("!", "！"),
+    ("\"", "”"),
+    ("#", "＃"),
+    ("$", "＄"),
+    ("%", "％"),
+    ("&", "＆"),
+    ("'", "’"),
+    ("(", "（"),
+    (")", "）"),
+    ("*", "＊"),
+    ("+", "＋"),
+    (",", "，"),
+    ("-", "－"),
+    (".", "．"),
+    ("/", "／"),
+    ("0", "０"),
+    ("1", "１"),
+    ("2", "２"),
+    ("3", "３"),
+    ("4", "４"),
+    ("5", "５"),
+    ("6", "６"),
+    ("7", "７"),
+    ("8", "８"),
+    ("9", "９"),
+    (":", "："),
+    (";", "；"),
+    ("<", "＜"),
+    ("=", "＝"),
+    (">", "＞"),
+    ("?", "？"),
+    ("@", "＠"),
+    ("A", "Ａ"),
+    ("B", "Ｂ"),
+    ("C", "Ｃ"),
+    ("D", "Ｄ"),
+    ("E", "Ｅ"),
+    ("F", "Ｆ"),
+    ("G", "Ｇ"),
+    ("H", "Ｈ"),
+    ("I", "Ｉ"),
+    ("J", "Ｊ"),
+    ("K", "Ｋ"),
+    ("L", "Ｌ"),
+    ("M", "Ｍ"),
+    ("N", "Ｎ"),
+    ("O", "Ｏ"),
+    ("P", "Ｐ"),
+    ("Q", "Ｑ"),
+    ("R", "Ｒ"),
+    ("S", "Ｓ"),
+    ("T", "Ｔ"),
+    ("U", "Ｕ"),
+    ("V", "Ｖ"),
+    ("W", "Ｗ"),
+    ("X", "Ｘ"),
+    ("Y", "Ｙ"),
+    ("Z", "Ｚ"),
+    ("[", "［"),
+    ("\\", "￥"),
+    ("]", "］"),
+    ("^", "＾"),
+    ("_", "＿"),
+    ("`", "‘"),
+    ("a", "ａ"),
+    ("b", "ｂ"),
+    ("c", "ｃ"),
+    ("d", "ｄ"),
+    ("e", "ｅ"),
+    ("f", "ｆ"),
+    ("g", "ｇ"),
+    ("h", "ｈ"),
+    ("i", "ｉ"),
+    ("j", "ｊ"),
+    ("k", "ｋ"),
+    ("l", "ｌ"),
+    ("m", "ｍ"),
+    ("n", "ｎ"),
+    ("o", "ｏ"),
+    ("p", "ｐ"),
+    ("q", "ｑ"),
+    ("r", "ｒ"),
+    ("s", "ｓ"),
+    ("t", "ｔ"),
+    ("u", "ｕ"),
+    ("v", "ｖ"),
+    ("w", "ｗ"),
+    ("x", "ｘ"),
+    ("y", "ｙ"),
+    ("z", "ｚ"),
+    ("{", "｛"),
+    ("|", "｜"),
+    ("}", "｝"),
+    ("~", "～"),
+    ("ｳﾞ", "ヴ"),
+    ("ｶﾞ", "ガ"),
+    ("ｷﾞ", "ギ"),
+    ("ｸﾞ", "グ"),
+    ("ｹﾞ", "ゲ"),
+    ("ｺﾞ", "ゴ"),
+    ("ｻﾞ", "ザ"),
+    ("ｼﾞ", "ジ"),
+    ("ｽﾞ", "ズ"),
+    ("ｾﾞ", "ゼ"),
+    ("ｿﾞ", "ゾ"),
+    ("ﾀﾞ", "ダ"),
+    ("ﾁﾞ", "ヂ"),
+    ("ﾂﾞ", "ヅ"),
+    ("ﾃﾞ", "デ"),
+    ("ﾄﾞ", "ド"),
+    ("ﾊﾞ", "バ"),
+    ("ﾋﾞ", "ビ"),
+    ("ﾌﾞ", "ブ"),
+    ("ﾍﾞ", "ベ"),
+    ("ﾎﾞ", "ボ"),
+    ("ﾊﾟ", "パ"),
+    ("ﾋﾟ", "ピ"),
+    ("ﾌﾟ", "プ"),
+    ("ﾍﾟ", "ペ"),
+    ("ﾎﾟ", "ポ"),
+    ("｡", "。"),
+    ("｢", "「"),
+    ("｣", "」"),
+    ("､", "、"),
+    ("･", "・"),
+    ("ｦ", "ヲ"),
+    ("ｧ", "ァ"),
+    ("ｨ", "ィ"),
+    ("ｩ", "ゥ"),
+    ("ｪ", "ェ"),
+    ("ｫ", "ォ"),
+    ("ｬ", "ャ"),
+    ("ｭ", "ュ"),
+    ("ｮ", "ョ"),
+    ("ｯ", "ッ"),
+    ("ｰ", "ー"),
+    ("ｱ", "ア"),
+    ("ｲ", "イ"),
+    ("ｳ", "ウ"),
+    ("ｴ", "エ"),
+    ("ｵ", "オ"),
+    ("ｶ", "カ"),
+    ("ｷ", "キ"),
+    ("ｸ", "ク"),
+    ("ｹ", "ケ"),
+    ("ｺ", "コ"),
+    ("ｻ", "サ"),
+    ("ｼ", "シ"),
+    ("ｽ", "ス"),
+    ("ｾ", "セ"),
+    ("ｿ", "ソ"),
+    ("ﾀ", "タ"),
+    ("ﾁ", "チ"),
+    ("ﾂ", "ツ"),
+    ("ﾃ", "テ"),
+    ("ﾄ", "ト"),
+    ("ﾅ", "ナ"),
+    ("ﾆ", "ニ"),
+    ("ﾇ", "ヌ"),
+    ("ﾈ", "ネ"),
+    ("ﾉ", "ノ"),
+    ("ﾊ", "ハ"),
+    ("ﾋ", "ヒ"),
+    ("ﾌ", "フ"),
+    ("ﾍ", "ヘ"),
+    ("ﾎ", "ホ"),
+    ("ﾏ", "マ"),
+    ("ﾐ", "ミ"),
+    ("ﾑ", "ム"),
+    ("ﾒ", "メ"),
+    ("ﾓ", "モ"),
+    ("ﾔ", "ヤ"),
+    ("ﾕ", "ユ"),
+    ("ﾖ", "ヨ"),
+    ("ﾗ", "ラ"),
+    ("ﾘ", "リ"),
+    ("ﾙ", "ル"),
+    ("ﾚ", "レ"),
+    ("ﾛ", "ロ"),
+    ("ﾜ", "ワ"),
+    ("ﾝ", "ン"),
+    ("ﾞ", "゛"),
+    ("ﾟ", "゜"),
+];
+
+/// 変換表に無い文字のうち、読み飛ばされる制御文字。
+pub(crate) fn is_control(c: char) -> bool {
+    matches!(c, '\u{00}'..='\u{1f}' | '\u{7f}')
+}
@@ -0,0 +1,310 @@
+use super::*;
+use camino::{Utf8Path, Utf8PathBuf};
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// naist-jdicにおける`名詞,固有名詞,一般,*`の文脈ID。
+pub const PROPER_NOUN_CONTEXT_ID: u16 = 1348;
+
+/// [`DictionaryEntry::new`]で使うコスト。
+pub const DEFAULT_COST: i16 = 5000;
+
+/// 辞書の項目。CSVの1行(`表層形,左文脈ID,右文脈ID,コスト,`に続けてMeCabの素性)に対応する。
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct DictionaryEntry {
+    pub left_id: u16,
+    pub right_id: u16,
+    pub cost: i16,
+    pub morpheme: Morpheme,
+}
+
+/// ユーザー辞書の単語の列。
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct UserDict {
+    pub entries: Vec<DictionaryEntry>,
+}
+
+/// [`UserDict::lint`]で見つかった問題。
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct LintIssue {
+    /// [`UserDict::entries`]での位置(0始まり)。
+    pub index: usize,
+    pub kind: LintKind,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum LintKind {
+    /// 発音がカタカナ(と`ー`)以外を含む。
+    InvalidPronunciation { pronunciation: String },
+    /// アクセント核の位置がモーラ数を超えている。
+    AccentOutOfRange { accent: i32, mora_count: usize },
+    /// モーラ数が発音から求めたものと一致しない。
+    MoraSizeMismatch { mora_size: i32, mora_count: usize },
+    /// 同じ表層形が`first`番目にもある。
+    DuplicateSurface { first: usize },
+}
+
+impl DictionaryEntry {
+    /// 固有名詞として登録する単語を作る。表層形はtext2mecabと同様に全角に変換する。
+    pub fn new(surface: &str, pronunciation: &str, accent_type: i32) -> Self {
+        let surface = Text2Mecab::default().convert(surface);
+        Self {
+            left_id: PROPER_NOUN_CONTEXT_ID,
+            right_id: PROPER_NOUN_CONTEXT_ID,
+            cost: DEFAULT_COST,
+            morpheme: Morpheme {
+                orig: surface.clone(),
+                string: surface,
+                pos: "名詞".to_owned(),
+                pos_group1: "固有名詞".to_owned(),
+                pos_group2: "一般".to_owned(),
+                pos_group3: "*".to_owned(),
+                ctype: "*".to_owned(),
+                cform: "*".to_owned(),
+                read: pronunciation.to_owned(),
+                pron: pronunciation.to_owned(),
+                acc: accent_type,
+                mora_size: split_moras(pronunciation).len() as i32,
+                chain_rule: "*".to_owned(),
+            },
+        }
+    }
+
+    pub fn parse_csv(line: &str) -> Result<Self> {
+        let error = || Error::Parse {
+            what: "user dictionary entry",
+            input: line.to_owned(),
+        };
+        let mut fields = line.splitn(5, ',');
+        let mut next = || fields.next().ok_or_else(error);
+        let surface = next()?;
+        let left_id = next()?.parse().map_err(|_| error())?;
+        let right_id = next()?.parse().map_err(|_| error())?;
+        let cost = next()?.parse().map_err(|_| error())?;
+        let features = next()?;
+        if features.split(',').count() < 11 {
+            return Err(error());
+        }
+        Ok(Self {
+            left_id,
+            right_id,
+            cost,
+            morpheme: Morpheme::parse(&format!("{surface},{features}")),
+        })
+    }
+
+    pub fn to_csv(&self) -> String {
+        let Morpheme {
+            string,
+            pos,
+            pos_group1,
+            pos_group2,
+            pos_group3,
+            ctype,
+            cform,
+            orig,
+            read,
+            pron,
+            acc,
+            mora_size,
+            chain_rule,
+        } = &self.morpheme;
+        format!(
+            "{string},{},{},{},{pos},{pos_group1},{pos_group2},{pos_group3},{ctype},{cform},{orig},{read},{pron},{acc}/{mora_size},{chain_rule}",
+            self.left_id, self.right_id, self.cost,
+        )
+    }
+}
+
+impl UserDict {
+    /// MeCabのユーザー辞書の形式のCSVを読む。空行は無視する。
+    pub fn parse_csv(csv: &str) -> Result<Self> {
+        csv.lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(DictionaryEntry::parse_csv)
+            .collect()
+    }
+
+    pub fn to_csv(&self) -> String {
+        self.entries
+            .iter()
+            .map(|entry| entry.to_csv() + "\n")
+            .collect()
+    }
+
+    pub fn lint(&self) -> Vec<LintIssue> {
+        let mut issues = vec![];
+        let mut surfaces = HashMap::new();
+        for (index, DictionaryEntry { morpheme, .. }) in self.entries.iter().enumerate() {
+            let mut push = |kind| issues.push(LintIssue { index, kind });
+            if morpheme.pron.is_empty() || !morpheme.pron.chars().all(is_katakana) {
+                push(LintKind::InvalidPronunciation {
+                    pronunciation: morpheme.pron.clone(),
+                });
+            }
+            let mora_count = split_moras(&morpheme.pron).len();
+            if morpheme.acc < 0 || morpheme.acc as usize > mora_count {
+                push(LintKind::AccentOutOfRange {
+                    accent: morpheme.acc,
+                    mora_count,
+                });
+            }
+            if morpheme.mora_size as usize != mora_count {
+                push(LintKind::MoraSizeMismatch {
+                    mora_size: morpheme.mora_size,
+                    mora_count,
+                });
+            }
+            if let Some(&first) = surfaces.get(&morpheme.string) {
+                push(LintKind::DuplicateSurface { first });
+            } else {
+                surfaces.insert(&morpheme.string, index);
+            }
+        }
+        issues
+    }
+
+    /// `dic_dir`のシステム辞書に合わせてコンパイルし、`out`に書き出す。
+    pub fn build(&self, dic_dir: impl AsRef<Utf8Path>, out: impl AsRef<Utf8Path>) -> Result<()> {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let temp_dir =
+            Utf8PathBuf::try_from(std::env::temp_dir()).map_err(|e| e.into_io_error())?;
+        let csv = temp_dir.join(format!(
+            "open_jtalk-user-dict-{}-{}.csv",
+            std::process::id(),
+            COUNT.fetch_add(1, Ordering::Relaxed),
+        ));
+        std::fs::write(&csv, self.to_csv())?;
+        let result = mecab_dict_index(&[
+            "mecab-dict-index",
+            "-d",
+            dic_dir.as_ref().as_str(),
+            "-u",
+            out.as_ref().as_str(),
+            "-f",
+            "utf-8",
+            "-t",
+            "utf-8",
+            csv.as_str(),
+        ]);
+        std::fs::remove_file(&csv)?;
+        result
+    }
+}
+
+impl FromIterator<DictionaryEntry> for UserDict {
+    fn from_iter<T: IntoIterator<Item = DictionaryEntry>>(iter: T) -> Self {
+        Self {
+            entries: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl fmt::Display for LintKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidPronunciation { pronunciation } => {
+                write!(f, "pronunciation {pronunciation:?} is not katakana")
+            }
+            Self::AccentOutOfRange { accent, mora_count } => write!(
+                f,
+                "accent {accent} is out of range for {mora_count} mora(s)"
+            ),
+            Self::MoraSizeMismatch {
+                mora_size,
+                mora_count,
+            } => write!(
+                f,
+                "mora size {mora_size} does not match the pronunciation ({mora_count})"
+            ),
+            Self::DuplicateSurface { first } => {
+                write!(f, "duplicate surface (first defined at entry {first})")
+            }
+        }
+    }
+}
+
+fn is_katakana(c: char) -> bool {
+    matches!(c, 'ァ'..='ヴ' | 'ー')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const LINE: &str = "ＶＯＩＣＥＶＯＸ,1348,1348,5000,名詞,固有名詞,一般,*,*,*,ＶＯＩＣＥＶＯＸ,ボイスボックス,ボイスボックス,5/7,*";
+
+    #[rstest]
+    fn dictionary_entry_new_works() {
+        let entry = DictionaryEntry::new("VOICEVOX", "ボイスボックス", 5);
+        assert_eq!(LINE, entry.to_csv());
+        assert_eq!(entry, DictionaryEntry::parse_csv(LINE).unwrap());
+    }
+
+    #[rstest]
+    #[case("")]
+    #[case("ＶＯＩＣＥＶＯＸ,1348,1348,5000")]
+    #[case("ＶＯＩＣＥＶＯＸ,x,1348,5000,名詞,固有名詞,一般,*,*,*,ＶＯＩＣＥＶＯＸ,ボイスボックス,ボイスボックス,5/7,*")]
+    #[case("ＶＯＩＣＥＶＯＸ,1348,1348,5000,名詞,固有名詞,一般")]
+    fn dictionary_entry_parse_csv_fails(#[case] line: &str) {
+        assert!(matches!(
+            DictionaryEntry::parse_csv(line),
+            Err(Error::Parse { .. })
+        ));
+    }
+
+    #[rstest]
+    fn user_dict_lint_works() {
+        let mut mismatch = DictionaryEntry::new("あ", "ア", 0);
+        mismatch.morpheme.mora_size = 2;
+        let dict = [
+            DictionaryEntry::new("VOICEVOX", "ボイスボックス", 5),
+            DictionaryEntry::new("ずんだ", "ずんだ", 0),
+            DictionaryEntry::new("もち", "モチ", 3),
+            DictionaryEntry::new("VOICEVOX", "ボイボ", 0),
+            mismatch,
+        ]
+        .into_iter()
+        .collect::<UserDict>();
+        assert_eq!(
+            vec![
+                LintIssue {
+                    index: 1,
+                    kind: LintKind::InvalidPronunciation {
+                        pronunciation: "ずんだ".to_owned()
+                    }
+                },
+                LintIssue {
+                    index: 2,
+                    kind: LintKind::AccentOutOfRange {
+                        accent: 3,
+                        mora_count: 2
+                    }
+                },
+                LintIssue {
+                    index: 3,
+                    kind: LintKind::DuplicateSurface { first: 0 }
+                },
+                LintIssue {
+                    index: 4,
+                    kind: LintKind::MoraSizeMismatch {
+                        mora_size: 2,
+                        mora_count: 1
+                    }
+                },
+            ],
+            dict.lint()
+        );
+    }
+
+    #[rstest]
+    fn user_dict_csv_round_trips() {
+        let dict = UserDict::parse_csv(&format!("{LINE}\n\n{LINE}\n")).unwrap();
+        assert_eq!(2, dict.entries.len());
+        assert_eq!(format!("{LINE}\n{LINE}\n"), dict.to_csv());
+    }
+}